@@ -1,4 +1,5 @@
 pub mod poll;
+pub mod resolver;
 pub mod socket;
 pub mod socket_wamr;
 #[cfg(feature = "wasi_poll")]
@@ -6,13 +7,20 @@ pub mod wasi_poll;
 #[cfg(not(feature = "wasi_poll"))]
 mod wasi_poll;
 
+pub use resolver::{Resolver, ResolverBuilder, Transport};
 pub use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr};
 use std::{
     io::{self, Read, Write},
     net::{SocketAddrV4, SocketAddrV6},
-    os::wasi::prelude::{AsRawFd, FromRawFd, IntoRawFd},
+    os::wasi::prelude::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd},
+    time::{Duration, Instant},
 };
 
+/// A TCP connection.
+///
+/// Converting it into an [`OwnedFd`] or a [`std::net::TcpStream`] hands out
+/// the descriptor, so it fails and gives the stream back while clones from
+/// [`TcpStream::try_clone`] or split halves are alive. Drop those first.
 #[derive(Debug)]
 pub struct TcpStream {
     s: socket::Socket,
@@ -30,6 +38,11 @@ impl AsMut<socket::Socket> for TcpStream {
     }
 }
 
+/// A TCP socket listening for connections.
+///
+/// Converting it into an [`OwnedFd`] or a [`std::net::TcpListener`] fails
+/// and gives the listener back while clones of its socket made with
+/// [`socket::Socket::try_clone`] are alive. Drop those first.
 #[derive(Debug)]
 pub struct TcpListener {
     s: socket::Socket,
@@ -49,6 +62,11 @@ impl AsMut<socket::Socket> for TcpListener {
     }
 }
 
+/// A UDP socket.
+///
+/// Converting it into an [`OwnedFd`] or a [`std::net::UdpSocket`] fails and
+/// gives the socket back while clones made with
+/// [`socket::Socket::try_clone`] are alive. Drop those first.
 #[derive(Debug)]
 pub struct UdpSocket {
     s: socket::Socket,
@@ -72,12 +90,25 @@ impl TcpStream {
     /// If multiple address is given, the first successful socket is
     /// returned.
     pub fn connect<A: ToSocketAddrs>(addrs: A) -> io::Result<TcpStream> {
+        TcpStream::connect_with(addrs, false)
+    }
+
+    /// Like [`TcpStream::connect`], with `TCP_FASTOPEN_CONNECT` set so that
+    /// data from the first write can be sent with the SYN.
+    pub fn connect_fastopen<A: ToSocketAddrs>(addrs: A) -> io::Result<TcpStream> {
+        TcpStream::connect_with(addrs, true)
+    }
+
+    fn connect_with<A: ToSocketAddrs>(addrs: A, fastopen: bool) -> io::Result<TcpStream> {
         let mut last_error = io::Error::from(io::ErrorKind::ConnectionRefused);
         let addrs = addrs.to_socket_addrs()?;
 
         let connect = |addrs| {
             let addr_family = socket::AddressFamily::from(&addrs);
             let s = socket::Socket::new(addr_family, socket::SocketType::Stream)?;
+            if fastopen {
+                s.set_tcp_fastopen_connect(true)?;
+            }
             s.connect(&addrs)?;
             Ok(s)
         };
@@ -91,10 +122,71 @@ impl TcpStream {
         return Err(last_error);
     }
 
+    /// Create a nonblocking TCP socket and start connecting to `addr`.
+    ///
+    /// The returned stream may still be connecting. Wait for it to become
+    /// writable with [`poll::poll`], then call [`TcpStream::connect_result`]
+    /// to find out whether the connection succeeded.
+    pub fn connect_nonblocking(addr: &SocketAddr) -> io::Result<TcpStream> {
+        let s = socket::Socket::new(addr.into(), socket::SocketType::Stream)?;
+        s.set_nonblocking(true)?;
+        match s.connect(addr) {
+            Ok(()) => {}
+            Err(e) if is_connect_in_progress(&e) => {}
+            Err(e) => return Err(e),
+        }
+        Ok(TcpStream { s })
+    }
+
+    /// Connect to `addr`, failing with `TimedOut` after `timeout`.
+    ///
+    /// A zero `timeout` is rejected with `InvalidInput`, like in std.
+    pub fn connect_timeout(addr: &SocketAddr, timeout: Duration) -> io::Result<TcpStream> {
+        check_timeout(Some(timeout))?;
+        let stream = TcpStream::connect_nonblocking(addr)?;
+        let deadline = std::time::SystemTime::now() + timeout;
+        let subs = [poll::Subscription::io(
+            0,
+            &stream,
+            false,
+            true,
+            Some(deadline),
+        )];
+
+        let events = poll::poll(&subs)?;
+        let mut result = Err(io::Error::from(io::ErrorKind::TimedOut));
+        for event in events {
+            match event.event_type {
+                poll::EventType::Write => {
+                    result = stream.s.connect_result();
+                    break;
+                }
+                poll::EventType::Error(e) => result = Err(e),
+                _ => {}
+            }
+        }
+        result?;
+
+        stream.set_nonblocking(false)?;
+        Ok(stream)
+    }
+
     pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
         self.s.shutdown(how)
     }
 
+    /// Whether `write_vectored` sends all buffers in a single call, which
+    /// it does. `Write::is_write_vectored` itself is not stable yet.
+    pub fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    /// Read data without removing it from the receive queue, so that a
+    /// following `read` returns it again.
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.s.peek(buf)
+    }
+
     /// Get peer address.
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
         self.s.get_peer()
@@ -109,11 +201,194 @@ impl TcpStream {
         self.s.set_nonblocking(nonblocking)
     }
 
+    /// Set the read timeout, `None` blocks indefinitely.
+    ///
+    /// A zero duration fails with `InvalidInput`, a timed out read with `WouldBlock`.
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        check_timeout(dur)?;
+        self.s.set_recv_timeout(dur)
+    }
+
+    /// Set the write timeout, `None` blocks indefinitely.
+    ///
+    /// A zero duration fails with `InvalidInput`, a timed out write with `WouldBlock`.
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        check_timeout(dur)?;
+        self.s.set_send_timeout(dur)
+    }
+
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.s.get_recv_timeout()
+    }
+
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.s.get_send_timeout()
+    }
+
+    /// Set `TCP_NODELAY`, disabling Nagle's algorithm when `true`.
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.s.set_nodelay(nodelay)
+    }
+
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.s.nodelay()
+    }
+
+    /// Set `TCP_QUICKACK`, sending ACKs immediately instead of delaying them.
+    pub fn set_quickack(&self, quickack: bool) -> io::Result<()> {
+        self.s.set_quickack(quickack)
+    }
+
+    pub fn quickack(&self) -> io::Result<bool> {
+        self.s.quickack()
+    }
+
+    /// Set `SO_LINGER`, `None` disables lingering on close.
+    ///
+    /// The time is rounded up to whole seconds.
+    pub fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+        self.s.set_linger(linger)
+    }
+
+    pub fn linger(&self) -> io::Result<Option<Duration>> {
+        self.s.linger()
+    }
+
+    /// Enable TCP keepalive with the given parameters, or disable it with `None`.
+    pub fn set_keepalive(&self, keepalive: Option<&TcpKeepalive>) -> io::Result<()> {
+        match keepalive {
+            Some(keepalive) => {
+                let was_enabled = self.s.keepalive()?;
+                self.s.set_keepalive(true)?;
+                let set_params = || {
+                    if let Some(time) = keepalive.time {
+                        self.s.set_tcp_keepidle(time)?;
+                    }
+                    if let Some(interval) = keepalive.interval {
+                        self.s.set_tcp_keepintvl(interval)?;
+                    }
+                    Ok(())
+                };
+                let result = set_params();
+                if result.is_err() {
+                    // Leave keepalive as it was before the call.
+                    let _ = self.s.set_keepalive(was_enabled);
+                }
+                result
+            }
+            None => self.s.set_keepalive(false),
+        }
+    }
+
+    pub fn keepalive(&self) -> io::Result<bool> {
+        self.s.keepalive()
+    }
+
+    /// Set `IP_TTL`, the time-to-live of outgoing packets.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.s.set_ttl(check_ttl(ttl)?)
+    }
+
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.s.ttl().map(u32::from)
+    }
+
+    /// Check whether a nonblocking connect that has become writable succeeded.
+    pub fn connect_result(&self) -> io::Result<()> {
+        self.s.connect_result()
+    }
+
+    /// Get and clear the pending socket error.
+    ///
+    /// This uses the legacy `sock_getsockopt` host call, so it can fail on
+    /// runtimes that only implement the WAMR socket ABI.
+    /// [`TcpStream::connect_result`] checks a connect without it.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.s.take_error()
+    }
+
+    /// Create a new handle to the connection, which is closed with the last handle.
+    pub fn try_clone(&self) -> io::Result<TcpStream> {
+        Ok(TcpStream {
+            s: self.s.try_clone()?,
+        })
+    }
+
+    /// Split the stream into a read half and a write half that can be moved
+    /// to different threads.
+    pub fn into_split(self) -> (OwnedReadHalf, OwnedWriteHalf) {
+        let r = OwnedReadHalf {
+            s: self.s.clone_handle(),
+        };
+        (r, OwnedWriteHalf { s: self.s })
+    }
+
+    /// Release the descriptor without closing it, or give the stream back
+    /// while clones or split halves of it are alive.
+    pub fn try_into_raw_fd(self) -> Result<std::os::wasi::prelude::RawFd, TcpStream> {
+        self.s.try_into_raw_fd().map_err(TcpStream::from)
+    }
+
     pub fn new(s: socket::Socket) -> Self {
         Self { s }
     }
 }
 
+fn check_timeout(dur: Option<Duration>) -> io::Result<()> {
+    if dur == Some(Duration::ZERO) {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot set a 0 duration timeout",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_ttl(ttl: u32) -> io::Result<u8> {
+    u8::try_from(ttl).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "ttl out of range"))
+}
+
+fn is_connect_in_progress(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::WouldBlock
+        || matches!(
+            e.raw_os_error(),
+            Some(libc::EINPROGRESS) | Some(libc::EALREADY)
+        )
+}
+
+/// TCP keepalive parameters for [`TcpStream::set_keepalive`].
+///
+/// Parameters that are not set keep the system defaults. Times are rounded
+/// up to whole seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TcpKeepalive {
+    time: Option<Duration>,
+    interval: Option<Duration>,
+}
+
+impl TcpKeepalive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Idle time before the first keepalive probe is sent (`TCP_KEEPIDLE`).
+    pub fn with_time(self, time: Duration) -> Self {
+        Self {
+            time: Some(time),
+            ..self
+        }
+    }
+
+    /// Time between keepalive probes (`TCP_KEEPINTVL`).
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self {
+            interval: Some(interval),
+            ..self
+        }
+    }
+}
+
 impl AsRawFd for TcpStream {
     fn as_raw_fd(&self) -> std::os::wasi::prelude::RawFd {
         self.s.as_raw_fd()
@@ -121,6 +396,9 @@ impl AsRawFd for TcpStream {
 }
 
 impl IntoRawFd for TcpStream {
+    /// # Panics
+    ///
+    /// If other handles to the socket are alive, see [`TcpStream::try_into_raw_fd`].
     fn into_raw_fd(self) -> std::os::wasi::prelude::RawFd {
         self.s.into_raw_fd()
     }
@@ -134,16 +412,69 @@ impl FromRawFd for TcpStream {
     }
 }
 
+impl AsFd for TcpStream {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.s.as_fd()
+    }
+}
+
+impl From<OwnedFd> for TcpStream {
+    fn from(fd: OwnedFd) -> Self {
+        TcpStream::from(socket::Socket::from(fd))
+    }
+}
+
+impl TryFrom<TcpStream> for OwnedFd {
+    type Error = TcpStream;
+
+    /// Fails, giving the socket back, while other handles to it are alive.
+    fn try_from(s: TcpStream) -> Result<Self, TcpStream> {
+        OwnedFd::try_from(s.s).map_err(TcpStream::from)
+    }
+}
+
+impl From<std::net::TcpStream> for TcpStream {
+    fn from(s: std::net::TcpStream) -> Self {
+        TcpStream::from(OwnedFd::from(s))
+    }
+}
+
+impl TryFrom<TcpStream> for std::net::TcpStream {
+    type Error = TcpStream;
+
+    fn try_from(s: TcpStream) -> Result<Self, TcpStream> {
+        OwnedFd::try_from(s).map(std::net::TcpStream::from)
+    }
+}
+
+fn recv_vectored(s: &socket::Socket, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+    let mut read_bufs: Vec<socket::IovecRead> = bufs
+        .iter_mut()
+        .map(|b| socket::IovecRead {
+            buf: b.as_mut_ptr(),
+            size: b.len(),
+        })
+        .collect();
+    s.recv_vectored(&mut read_bufs, socket::MsgFlags::empty())
+        .map(|(n, _)| n)
+}
+
 impl Read for TcpStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.s.recv(buf)
     }
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        recv_vectored(&self.s, bufs)
+    }
 }
 
 impl Write for TcpStream {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.s.send(buf)
     }
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.s.send_vectored(bufs, socket::SendFlags::empty())
+    }
     fn flush(&mut self) -> io::Result<()> {
         Ok(())
     }
@@ -153,12 +484,18 @@ impl Read for &TcpStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.s.recv(buf)
     }
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        recv_vectored(&self.s, bufs)
+    }
 }
 
 impl Write for &TcpStream {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.s.send(buf)
     }
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.s.send_vectored(bufs, socket::SendFlags::empty())
+    }
     fn flush(&mut self) -> io::Result<()> {
         Ok(())
     }
@@ -170,33 +507,163 @@ impl From<socket::Socket> for TcpStream {
     }
 }
 
-impl TcpListener {
+/// The read half of a [`TcpStream`], created by [`TcpStream::into_split`].
+#[derive(Debug)]
+pub struct OwnedReadHalf {
+    s: socket::Socket,
+}
+
+impl OwnedReadHalf {
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.s.peek(buf)
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.s.get_peer()
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.s.get_local()
+    }
+}
+
+impl Read for OwnedReadHalf {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.s.recv(buf)
+    }
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        recv_vectored(&self.s, bufs)
+    }
+}
+
+/// The write half of a [`TcpStream`], created by [`TcpStream::into_split`].
+#[derive(Debug)]
+pub struct OwnedWriteHalf {
+    s: socket::Socket,
+}
+
+impl OwnedWriteHalf {
+    /// Shut down the write side of the connection, the read half keeps working.
+    pub fn shutdown(&self) -> io::Result<()> {
+        self.s.shutdown(Shutdown::Write)
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.s.get_peer()
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.s.get_local()
+    }
+}
+
+impl Write for OwnedWriteHalf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.s.send(buf)
+    }
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.s.send_vectored(bufs, socket::SendFlags::empty())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Builder for a [`TcpListener`] with non-default socket options.
+///
+/// ```no_run
+/// use wasmedge_wasi_socket::TcpListenerBuilder;
+///
+/// let listener = TcpListenerBuilder::new()
+///     .backlog(1024)
+///     .reuse_port(true)
+///     .bind("0.0.0.0:0")?;
+/// println!("listening on {}", listener.local_addr()?);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TcpListenerBuilder {
+    backlog: i32,
+    reuse_addr: bool,
+    reuse_port: bool,
+    ipv6_only: Option<bool>,
+    nonblocking: bool,
+}
+
+impl Default for TcpListenerBuilder {
+    fn default() -> Self {
+        TcpListenerBuilder {
+            backlog: 128,
+            reuse_addr: true,
+            reuse_port: false,
+            ipv6_only: None,
+            nonblocking: false,
+        }
+    }
+}
+
+impl TcpListenerBuilder {
+    /// Create a builder with the same options as [`TcpListener::bind`]: a
+    /// backlog of 128 and `SO_REUSEADDR` set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum length of the queue of pending connections.
+    pub fn backlog(mut self, backlog: i32) -> Self {
+        self.backlog = backlog;
+        self
+    }
+
+    pub fn reuse_addr(mut self, reuse_addr: bool) -> Self {
+        self.reuse_addr = reuse_addr;
+        self
+    }
+
+    pub fn reuse_port(mut self, reuse_port: bool) -> Self {
+        self.reuse_port = reuse_port;
+        self
+    }
+
+    /// Set `IPV6_V6ONLY` on IPv6 listeners, it is ignored for IPv4 addresses.
+    /// The system default is kept when this is not called.
+    pub fn ipv6_only(mut self, ipv6_only: bool) -> Self {
+        self.ipv6_only = Some(ipv6_only);
+        self
+    }
+
+    pub fn nonblocking(mut self, nonblocking: bool) -> Self {
+        self.nonblocking = nonblocking;
+        self
+    }
+
     /// Create TCP socket and bind to the given address.
     ///
     /// If multiple address is given, the first successful socket is
     /// returned.
-    pub fn bind<A: ToSocketAddrs>(addrs: A, nonblocking: bool) -> io::Result<TcpListener> {
+    pub fn bind<A: ToSocketAddrs>(&self, addrs: A) -> io::Result<TcpListener> {
         let mut last_error = io::Error::from(io::ErrorKind::Other);
         let addrs = addrs.to_socket_addrs()?;
 
-        let bind = |addrs, nonblocking| {
-            let addr_family = socket::AddressFamily::from(&addrs);
-            let s = socket::Socket::new(addr_family, socket::SocketType::Stream)?;
-            s.setsockopt_socket(socket_wamr::SocketOptName::SoReuseaddr(true))?;
+        let bind = |addrs: SocketAddr| {
+            let s = socket::Socket::new((&addrs).into(), socket::SocketType::Stream)?;
+            if self.reuse_addr {
+                s.setsockopt_socket(socket_wamr::SocketOptName::SoReuseaddr(true))?;
+            }
+            if self.reuse_port {
+                s.setsockopt_socket(socket_wamr::SocketOptName::SoReuseport(true))?;
+            }
+            if let (Some(ipv6_only), SocketAddr::V6(_)) = (self.ipv6_only, addrs) {
+                s.set_ipv6_only(ipv6_only)?;
+            }
             s.bind(&addrs)?;
-            s.listen(128)?;
-            s.set_nonblocking(nonblocking)?;
-
-            let port = addrs.port();
-            Ok(TcpListener {
-                s,
-                address: Ok(addrs),
-                port: Some(port),
-            })
+            s.listen(self.backlog)?;
+            s.set_nonblocking(self.nonblocking)?;
+            Ok(TcpListener::from(s))
         };
 
         for addr in addrs {
-            match bind(addr, nonblocking) {
+            match bind(addr) {
                 Ok(tcp_listener) => return Ok(tcp_listener),
                 Err(e) => last_error = e,
             }
@@ -204,6 +671,22 @@ impl TcpListener {
 
         return Err(last_error);
     }
+}
+
+impl TcpListener {
+    /// Create TCP socket and bind to the given address.
+    ///
+    /// If multiple address is given, the first successful socket is
+    /// returned. Use [`TcpListenerBuilder`] to change the socket options.
+    pub fn bind<A: ToSocketAddrs>(addrs: A, nonblocking: bool) -> io::Result<TcpListener> {
+        TcpListenerBuilder::new()
+            .nonblocking(nonblocking)
+            .bind(addrs)
+    }
+
+    pub fn builder() -> TcpListenerBuilder {
+        TcpListenerBuilder::new()
+    }
 
     /// Accept incoming connections with given file descriptor flags.
     pub fn accept(&self, nonblocking: bool) -> io::Result<(TcpStream, SocketAddr)> {
@@ -221,6 +704,50 @@ impl TcpListener {
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         self.s.get_local()
     }
+
+    /// Set `IP_TTL`, the time-to-live of outgoing packets.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.s.set_ttl(check_ttl(ttl)?)
+    }
+
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.s.ttl().map(u32::from)
+    }
+
+    /// Get and clear the pending socket error.
+    ///
+    /// Like [`TcpStream::take_error`], this can fail on WAMR-ABI runtimes.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.s.take_error()
+    }
+
+    /// Set `IPV6_V6ONLY`.
+    ///
+    /// This only has an effect before the socket is bound, so a dual-stack
+    /// listener accepting both IPv4-mapped and native IPv6 clients is
+    /// created with [`TcpListenerBuilder::ipv6_only`]:
+    ///
+    /// ```no_run
+    /// use wasmedge_wasi_socket::TcpListenerBuilder;
+    ///
+    /// let listener = TcpListenerBuilder::new().ipv6_only(false).bind("[::]:8080")?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn set_only_v6(&self, only_v6: bool) -> io::Result<()> {
+        self.s.set_ipv6_only(only_v6)
+    }
+
+    pub fn only_v6(&self) -> io::Result<bool> {
+        self.s.ipv6_only()
+    }
+
+    /// Release the descriptor without closing it, or give the listener back
+    /// while other handles to it are alive.
+    pub fn try_into_raw_fd(self) -> Result<std::os::wasi::prelude::RawFd, TcpListener> {
+        let TcpListener { s, address, port } = self;
+        s.try_into_raw_fd()
+            .map_err(|s| TcpListener { s, address, port })
+    }
 }
 
 impl AsRawFd for TcpListener {
@@ -230,6 +757,9 @@ impl AsRawFd for TcpListener {
 }
 
 impl IntoRawFd for TcpListener {
+    /// # Panics
+    ///
+    /// If other handles to the socket are alive, see [`TcpListener::try_into_raw_fd`].
     fn into_raw_fd(self) -> std::os::wasi::prelude::RawFd {
         self.s.into_raw_fd()
     }
@@ -238,21 +768,53 @@ impl IntoRawFd for TcpListener {
 impl FromRawFd for TcpListener {
     unsafe fn from_raw_fd(fd: std::os::wasi::prelude::RawFd) -> Self {
         let s: socket::Socket = FromRawFd::from_raw_fd(fd);
-        match s.get_local() {
-            Ok(address) => {
-                let port = address.port();
-                TcpListener {
-                    s,
-                    address: Ok(address),
-                    port: Some(port),
-                }
-            }
-            Err(error) => TcpListener {
-                s,
-                address: Err(error),
-                port: None,
-            },
-        }
+        TcpListener::from(s)
+    }
+}
+
+impl AsFd for TcpListener {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.s.as_fd()
+    }
+}
+
+impl From<OwnedFd> for TcpListener {
+    fn from(fd: OwnedFd) -> Self {
+        TcpListener::from(socket::Socket::from(fd))
+    }
+}
+
+impl TryFrom<TcpListener> for OwnedFd {
+    type Error = TcpListener;
+
+    /// Fails, giving the socket back, while other handles to it are alive.
+    fn try_from(l: TcpListener) -> Result<Self, TcpListener> {
+        let TcpListener { s, address, port } = l;
+        OwnedFd::try_from(s).map_err(|s| TcpListener { s, address, port })
+    }
+}
+
+impl From<std::net::TcpListener> for TcpListener {
+    fn from(s: std::net::TcpListener) -> Self {
+        TcpListener::from(OwnedFd::from(s))
+    }
+}
+
+impl TryFrom<TcpListener> for std::net::TcpListener {
+    type Error = TcpListener;
+
+    fn try_from(s: TcpListener) -> Result<Self, TcpListener> {
+        OwnedFd::try_from(s).map(std::net::TcpListener::from)
+    }
+}
+
+impl From<socket::Socket> for TcpListener {
+    /// The `address` and `port` fields are filled from the address the
+    /// socket is actually bound to.
+    fn from(s: socket::Socket) -> Self {
+        let address = s.get_local();
+        let port = address.as_ref().ok().map(SocketAddr::port);
+        TcpListener { s, address, port }
     }
 }
 
@@ -297,9 +859,25 @@ impl UdpSocket {
 
         return Err(last_error);
     }
+
+    /// Receive a datagram, returning the number of bytes read and the source address.
     pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
         self.s.recv_from(buf)
     }
+
+    /// Receive a datagram with the given flags, also returning the flags
+    /// reported by the runtime.
+    ///
+    /// Check [`socket::RecvFlags::is_truncated`] to detect datagrams that did
+    /// not fit into `buf`.
+    pub fn recv_from_with_flags(
+        &self,
+        buf: &mut [u8],
+        flags: socket::MsgFlags,
+    ) -> io::Result<(usize, SocketAddr, socket::RecvFlags)> {
+        self.s
+            .recv_from_with_flags(socket::as_uninit_mut(buf), flags)
+    }
     pub fn send_to<A: ToSocketAddrs>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
         let addr = addr
             .to_socket_addrs()?
@@ -308,6 +886,194 @@ impl UdpSocket {
 
         self.s.send_to(buf, addr)
     }
+
+    /// Connect the socket to a remote address, so that `send` and `recv` can
+    /// be used and datagrams from other addresses are filtered out.
+    ///
+    /// If multiple address is given, the first one that the socket can
+    /// connect to is used.
+    pub fn connect<A: ToSocketAddrs>(&self, addrs: A) -> io::Result<()> {
+        let mut last_error = io::Error::new(io::ErrorKind::InvalidInput, "No address.");
+        for addr in addrs.to_socket_addrs()? {
+            match self.s.connect(&addr) {
+                Ok(()) => return Ok(()),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Send a datagram to the connected peer.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.s.send(buf)
+    }
+
+    /// Receive a datagram from the connected peer.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.s.recv(buf)
+    }
+
+    /// Receive a datagram from the connected peer without removing it from
+    /// the queue.
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.s.peek(buf)
+    }
+
+    /// Receive a datagram without removing it from the queue, returning the
+    /// number of bytes read and the source address.
+    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.s.peek_from(buf)
+    }
+
+    /// Get the address of the connected peer.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.s.get_peer()
+    }
+
+    /// Get local address.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.s.get_local()
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.s.set_nonblocking(nonblocking)
+    }
+
+    /// Set the read timeout, `None` blocks indefinitely.
+    ///
+    /// A zero duration fails with `InvalidInput`, a timed out read with `WouldBlock`.
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        check_timeout(dur)?;
+        self.s.set_recv_timeout(dur)
+    }
+
+    /// Set the write timeout, `None` blocks indefinitely.
+    ///
+    /// A zero duration fails with `InvalidInput`, a timed out write with `WouldBlock`.
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        check_timeout(dur)?;
+        self.s.set_send_timeout(dur)
+    }
+
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.s.get_recv_timeout()
+    }
+
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.s.get_send_timeout()
+    }
+
+    /// Join the IPv4 multicast group `multiaddr` on the local interface with
+    /// address `interface`, `Ipv4Addr::UNSPECIFIED` lets the system choose.
+    pub fn join_multicast_v4(&self, multiaddr: &Ipv4Addr, interface: &Ipv4Addr) -> io::Result<()> {
+        self.s.join_multicast(
+            IpAddr::V4(*multiaddr),
+            u32::from_ne_bytes(interface.octets()),
+        )
+    }
+
+    /// Join the IPv6 multicast group `multiaddr` on the interface with index
+    /// `interface`, `0` lets the system choose.
+    pub fn join_multicast_v6(&self, multiaddr: &Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.s.join_multicast(IpAddr::V6(*multiaddr), interface)
+    }
+
+    pub fn leave_multicast_v4(&self, multiaddr: &Ipv4Addr, interface: &Ipv4Addr) -> io::Result<()> {
+        self.s.leave_multicast(
+            IpAddr::V4(*multiaddr),
+            u32::from_ne_bytes(interface.octets()),
+        )
+    }
+
+    pub fn leave_multicast_v6(&self, multiaddr: &Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.s.leave_multicast(IpAddr::V6(*multiaddr), interface)
+    }
+
+    pub fn set_multicast_loop_v4(&self, multicast_loop_v4: bool) -> io::Result<()> {
+        self.s.set_multicast_loop(false, multicast_loop_v4)
+    }
+
+    pub fn multicast_loop_v4(&self) -> io::Result<bool> {
+        self.s.multicast_loop(false)
+    }
+
+    pub fn set_multicast_loop_v6(&self, multicast_loop_v6: bool) -> io::Result<()> {
+        self.s.set_multicast_loop(true, multicast_loop_v6)
+    }
+
+    pub fn multicast_loop_v6(&self) -> io::Result<bool> {
+        self.s.multicast_loop(true)
+    }
+
+    /// Set the time-to-live of outgoing IPv4 multicast packets, at most 255.
+    pub fn set_multicast_ttl_v4(&self, multicast_ttl_v4: u32) -> io::Result<()> {
+        self.s.set_multicast_ttl(check_ttl(multicast_ttl_v4)?)
+    }
+
+    pub fn multicast_ttl_v4(&self) -> io::Result<u32> {
+        self.s.multicast_ttl().map(u32::from)
+    }
+
+    /// Set `IP_TTL`, the time-to-live of outgoing packets.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.s.set_ttl(check_ttl(ttl)?)
+    }
+
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.s.ttl().map(u32::from)
+    }
+
+    /// Set `SO_BROADCAST`, allowing datagrams to be sent to a broadcast address.
+    pub fn set_broadcast(&self, broadcast: bool) -> io::Result<()> {
+        self.s.set_broadcast(broadcast)
+    }
+
+    pub fn broadcast(&self) -> io::Result<bool> {
+        self.s.broadcast()
+    }
+
+    /// Get and clear the pending socket error, e.g. an ICMP error reported
+    /// for an earlier datagram.
+    ///
+    /// Like [`TcpStream::take_error`], this can fail on WAMR-ABI runtimes.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.s.take_error()
+    }
+
+    /// Set `IPV6_V6ONLY`.
+    ///
+    /// This only has an effect before the socket is bound, so a dual-stack
+    /// socket is set up on a [`socket::Socket`] and converted afterwards:
+    ///
+    /// ```no_run
+    /// use wasmedge_wasi_socket::socket::{AddressFamily, Socket, SocketType};
+    /// use wasmedge_wasi_socket::UdpSocket;
+    ///
+    /// let s = Socket::new(AddressFamily::Inet6, SocketType::Datagram)?;
+    /// s.set_ipv6_only(false)?;
+    /// s.bind(&"[::]:5353".parse().unwrap())?;
+    /// let udp = UdpSocket::from(s);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn set_only_v6(&self, only_v6: bool) -> io::Result<()> {
+        self.s.set_ipv6_only(only_v6)
+    }
+
+    pub fn only_v6(&self) -> io::Result<bool> {
+        self.s.ipv6_only()
+    }
+
+    /// Release the descriptor without closing it, or give the socket back
+    /// while other handles to it are alive.
+    pub fn try_into_raw_fd(self) -> Result<std::os::wasi::prelude::RawFd, UdpSocket> {
+        self.s.try_into_raw_fd().map_err(UdpSocket::from)
+    }
+}
+
+impl From<socket::Socket> for UdpSocket {
+    fn from(s: socket::Socket) -> Self {
+        UdpSocket { s }
+    }
 }
 
 impl AsRawFd for UdpSocket {
@@ -316,71 +1082,157 @@ impl AsRawFd for UdpSocket {
     }
 }
 
-pub fn nslookup(node: &str, service: &str) -> std::io::Result<Vec<SocketAddr>> {
-    let dns_server = std::env::var("DNS_SERVER");
-    if let Ok(dns_server) = dns_server {
-        nslookup_with_dns_server(&dns_server, node, service)
-    } else {
-        nslookup_with_host(node, service)
+impl IntoRawFd for UdpSocket {
+    /// # Panics
+    ///
+    /// If other handles to the socket are alive, see [`UdpSocket::try_into_raw_fd`].
+    fn into_raw_fd(self) -> std::os::wasi::prelude::RawFd {
+        self.s.into_raw_fd()
+    }
+}
+
+impl FromRawFd for UdpSocket {
+    unsafe fn from_raw_fd(fd: std::os::wasi::prelude::RawFd) -> Self {
+        let s: socket::Socket = FromRawFd::from_raw_fd(fd);
+        UdpSocket::from(s)
+    }
+}
+
+impl AsFd for UdpSocket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.s.as_fd()
+    }
+}
+
+impl From<OwnedFd> for UdpSocket {
+    fn from(fd: OwnedFd) -> Self {
+        UdpSocket::from(socket::Socket::from(fd))
+    }
+}
+
+impl TryFrom<UdpSocket> for OwnedFd {
+    type Error = UdpSocket;
+
+    /// Fails, giving the socket back, while other handles to it are alive.
+    fn try_from(s: UdpSocket) -> Result<Self, UdpSocket> {
+        OwnedFd::try_from(s.s).map_err(UdpSocket::from)
+    }
+}
+
+impl From<std::net::UdpSocket> for UdpSocket {
+    fn from(s: std::net::UdpSocket) -> Self {
+        UdpSocket::from(OwnedFd::from(s))
+    }
+}
+
+impl TryFrom<UdpSocket> for std::net::UdpSocket {
+    type Error = UdpSocket;
+
+    fn try_from(s: UdpSocket) -> Result<Self, UdpSocket> {
+        OwnedFd::try_from(s).map(std::net::UdpSocket::from)
     }
 }
 
+/// Resolve `node` with the process-wide [`Resolver`].
+///
+/// This is the host resolver unless `DNS_SERVER` is set or another resolver
+/// was installed, see [`Resolver::default_resolver`].
+pub fn nslookup(node: &str, service: &str) -> std::io::Result<Vec<SocketAddr>> {
+    Resolver::default_resolver().lookup(node, service)
+}
+
+/// Resolve `node` with the host resolver, returning both IPv4 and IPv6 addresses.
 pub fn nslookup_with_host(node: &str, service: &str) -> std::io::Result<Vec<SocketAddr>> {
+    nslookup_with_host_family(node, service, socket::AddressFamily::Unspec)
+}
+
+/// Resolve `node` with the host resolver, restricted to the given address family.
+///
+/// `AddressFamily::Unspec` returns addresses of both families.
+pub fn nslookup_with_host_family(
+    node: &str,
+    service: &str,
+    family: socket::AddressFamily,
+) -> std::io::Result<Vec<SocketAddr>> {
     use socket::WasiAddrinfo;
-    let hints: WasiAddrinfo = WasiAddrinfo::default();
+    let mut hints: WasiAddrinfo = WasiAddrinfo::default();
+    hints.ai_family = family;
     let addrinfos = WasiAddrinfo::get_addrinfo(&node, &service, &hints, 10)?;
 
     let mut r_addrs = vec![];
-    for i in 0..addrinfos.len() {
-        let addrinfo = &addrinfos[i];
-
-        let addr = match addrinfo.addr.kind {
-            socket_wamr::WasiAddrType::IPv4 => {
-                let port = unsafe { addrinfo.addr.addr.ip4.port };
-                let wamr_ipv4_addr = unsafe { addrinfo.addr.addr.ip4.addr };
-                let ip = Ipv4Addr::new(
-                    wamr_ipv4_addr.n0,
-                    wamr_ipv4_addr.n1,
-                    wamr_ipv4_addr.n2,
-                    wamr_ipv4_addr.n3,
-                );
-                SocketAddr::V4(SocketAddrV4::new(ip, port))
-            }
-            socket_wamr::WasiAddrType::IPv6 => {
-                //unimplemented!("not support IPv6")
-                continue;
-            }
-        };
-
-        r_addrs.push(addr);
+    for addrinfo in addrinfos.iter() {
+        let addr = SocketAddr::from(&addrinfo.addr);
+        if !r_addrs.contains(&addr) {
+            r_addrs.push(addr);
+        }
     }
     Ok(r_addrs)
 }
 
+/// Which addresses a DNS lookup returns when a name has both IPv4 and IPv6
+/// addresses, and in which order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddressPreference {
+    /// Both families, IPv4 addresses first.
+    #[default]
+    Ipv4First,
+    /// Both families, IPv6 addresses first.
+    Ipv6First,
+    Ipv4Only,
+    Ipv6Only,
+}
+
+impl AddressPreference {
+    fn wants_ipv4(self) -> bool {
+        self != AddressPreference::Ipv6Only
+    }
+
+    fn wants_ipv6(self) -> bool {
+        self != AddressPreference::Ipv4Only
+    }
+
+    fn sort(self, addrs: &mut [SocketAddr]) {
+        match self {
+            AddressPreference::Ipv6First => addrs.sort_by_key(SocketAddr::is_ipv4),
+            _ => addrs.sort_by_key(SocketAddr::is_ipv6),
+        }
+    }
+}
+
+/// Resolve `node` by querying `dns_server` directly, for both A and AAAA
+/// records with IPv4 addresses first.
+///
+/// The query is sent over UDP first and only retried over TCP when the
+/// server marks its answer as truncated. If `dns_server` does not answer,
+/// the nameservers in `/etc/resolv.conf` are tried.
 pub fn nslookup_with_dns_server(
     dns_server: &str,
     node: &str,
-    _service: &str,
+    service: &str,
 ) -> std::io::Result<Vec<SocketAddr>> {
-    let mut conn = TcpStream::connect(dns_server)?;
-    let timeout = std::time::Duration::from_secs(5);
-    let _ignore = conn.as_mut().set_send_timeout(Some(timeout));
-    let _ignore = conn.as_mut().set_recv_timeout(Some(timeout));
-
-    if node == "localhost" {
-        return ("127.0.0.1", 0u16).to_socket_addrs().map(|v| v.collect());
-    }
-    let r = resolve::<_, Ipv4Addr>(&mut conn, node)
-        .unwrap_or_default()
-        .into_iter()
-        .map(|addr| (addr, 0).into())
-        .collect::<Vec<SocketAddr>>();
-    if r.is_empty() {
-        // __WASI_ERRNO_AINODATA
-        Err(std::io::Error::from_raw_os_error(83))
-    } else {
-        Ok(r)
+    nslookup_with_dns_server_preference(dns_server, node, service, AddressPreference::default())
+}
+
+/// Resolve `node` by querying `dns_server` directly, returning the address
+/// families selected by `preference`.
+///
+/// Search domains, timeouts and attempts are taken from `/etc/resolv.conf`
+/// when it can be read. The addresses of `dns_server` are tried first, then
+/// the nameservers listed there.
+pub fn nslookup_with_dns_server_preference(
+    dns_server: &str,
+    node: &str,
+    service: &str,
+    preference: AddressPreference,
+) -> std::io::Result<Vec<SocketAddr>> {
+    let servers: Vec<SocketAddr> = dns_server.to_socket_addrs()?.collect();
+    if servers.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid dns server address",
+        ));
     }
+    Resolver::system_with_nameservers(servers, preference).lookup(node, service)
 }
 
 pub trait ToQType: Sized {
@@ -417,52 +1269,264 @@ impl ToQType for Ipv6Addr {
     }
 }
 
-pub fn resolve<S: Write + Read, T: ToQType>(
-    conn: &mut S,
-    name: &str,
-) -> Result<Vec<T>, Box<dyn std::error::Error>> {
-    use dns_parser::QueryClass;
-    use dns_parser::{Builder, Packet, ResponseCode};
+/// Error returned by [`resolve`] and [`resolve_udp`].
+#[derive(Debug)]
+pub enum ResolveError {
+    Io(io::Error),
+    /// No complete response arrived in time.
+    TimedOut,
+    /// The name cannot be encoded in a query.
+    InvalidName,
+    /// The response could not be parsed.
+    Parse(dns_parser::Error),
+    /// The response id does not match the query, or the message is not a
+    /// response at all.
+    IdMismatch,
+    /// The response answers a different question than the one asked.
+    QuestionMismatch,
+    /// The server answered with an error code, e.g. `NameError` when the
+    /// name does not exist.
+    Response(dns_parser::ResponseCode),
+    /// The server does not offer recursion and had no answer itself.
+    RecursionUnavailable,
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolveError::Io(e) => write!(f, "{}", e),
+            ResolveError::TimedOut => f.write_str("dns query timed out"),
+            ResolveError::InvalidName => f.write_str("invalid dns name"),
+            ResolveError::Parse(e) => write!(f, "malformed dns response: {}", e),
+            ResolveError::IdMismatch => f.write_str("dns response id does not match the query"),
+            ResolveError::QuestionMismatch => {
+                f.write_str("dns response does not match the question")
+            }
+            ResolveError::Response(code) => write!(f, "dns server error: {}", code),
+            ResolveError::RecursionUnavailable => {
+                f.write_str("dns server does not support recursion")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResolveError::Io(e) => Some(e),
+            ResolveError::Parse(e) => Some(e),
+            ResolveError::Response(code) => Some(code),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ResolveError {
+    /// Read and write timeouts of the underlying socket become `TimedOut`.
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => ResolveError::TimedOut,
+            _ => ResolveError::Io(e),
+        }
+    }
+}
+
+impl From<dns_parser::Error> for ResolveError {
+    fn from(e: dns_parser::Error) -> Self {
+        ResolveError::Parse(e)
+    }
+}
+
+impl From<ResolveError> for io::Error {
+    fn from(e: ResolveError) -> Self {
+        match e {
+            ResolveError::Io(e) => e,
+            ResolveError::TimedOut => io::Error::new(io::ErrorKind::TimedOut, e),
+            ResolveError::InvalidName => io::Error::new(io::ErrorKind::InvalidInput, e),
+            _ => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+fn build_query<T: ToQType>(name: &str) -> Result<(u16, Vec<u8>), ResolveError> {
+    use dns_parser::{Builder, QueryClass};
     let id = rand::random();
     let mut builder = Builder::new_query(id, true);
     builder.add_question(name, false, T::q_type(), QueryClass::IN);
-    let packet = builder.build().map_err(|_| "truncated packet")?;
-    let mut psize = (packet.len() as u16).to_be_bytes();
+    let packet = builder.build().map_err(|_| ResolveError::InvalidName)?;
+    Ok((id, packet))
+}
 
-    conn.write_all(&psize[..])?;
-    conn.write_all(&packet)?;
+fn same_name(a: &str, b: &str) -> bool {
+    a.trim_end_matches('.')
+        .eq_ignore_ascii_case(b.trim_end_matches('.'))
+}
 
-    let n = conn.read(&mut psize)?;
-    if n < 2 {
-        return Err("Partial packet received".into());
-    }
+/// Check that `buf` answers the query `id` for `name` and collect the
+/// addresses for it, following CNAME records. Records for other names are
+/// ignored.
+fn parse_response<T: ToQType>(id: u16, name: &str, buf: &[u8]) -> Result<Vec<T>, ResolveError> {
+    use dns_parser::{Class, Packet, QueryClass, RData, ResponseCode};
+    let pkt = Packet::parse(buf)?;
 
-    let psize = u16::from_be_bytes(psize) as usize;
-    let mut buf = vec![0u8; psize];
-    let n = conn.read(&mut buf[0..psize])?;
-    if n != psize {
-        return Err("Partial packet received".into());
+    if pkt.header.id != id || pkt.header.query {
+        return Err(ResolveError::IdMismatch);
     }
 
-    let pkt = Packet::parse(&buf)?;
-
-    if pkt.header.id != id {
-        return Err("Illegal id".into());
+    let question_matches = match pkt.questions.as_slice() {
+        [q] => {
+            q.qtype == T::q_type()
+                && q.qclass == QueryClass::IN
+                && same_name(&q.qname.to_string(), name)
+        }
+        _ => false,
+    };
+    if !question_matches {
+        return Err(ResolveError::QuestionMismatch);
     }
 
     if pkt.header.response_code != ResponseCode::NoError {
-        return Err(pkt.header.response_code.into());
+        return Err(ResolveError::Response(pkt.header.response_code));
     }
 
+    // Without answers, an authority section (e.g. the SOA of NODATA) shows
+    // the server knew about the name even if it does not recurse.
+    if pkt.header.recursion_desired
+        && !pkt.header.recursion_available
+        && pkt.answers.is_empty()
+        && pkt.nameservers.is_empty()
+    {
+        return Err(ResolveError::RecursionUnavailable);
+    }
+
+    let mut names = vec![name.to_string()];
     let mut address = vec![];
     for ans in pkt.answers {
-        if let Some(addr) = T::from_rr(ans.data) {
+        let owner = ans.name.to_string();
+        if ans.cls != Class::IN || !names.iter().any(|n| same_name(n, &owner)) {
+            continue;
+        }
+        if let RData::CNAME(ref cname) = ans.data {
+            names.push(cname.0.to_string());
+        } else if let Some(addr) = T::from_rr(ans.data) {
             address.push(addr);
         }
     }
+
     Ok(address)
 }
 
+/// Fill `buf` completely, failing with `TimedOut` once `deadline` passes.
+fn read_full<S: Read>(
+    conn: &mut S,
+    mut buf: &mut [u8],
+    deadline: Option<Instant>,
+) -> Result<(), ResolveError> {
+    while !buf.is_empty() {
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            return Err(ResolveError::TimedOut);
+        }
+        match conn.read(buf) {
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            Ok(n) => buf = &mut buf[n..],
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Send a query for `name` over a TCP connection to a DNS server.
+///
+/// Each read is bounded by the stream's own read timeout.
+pub fn resolve<S: Write + Read, T: ToQType>(
+    conn: &mut S,
+    name: &str,
+) -> Result<Vec<T>, ResolveError> {
+    resolve_tcp(conn, name, None)
+}
+
+fn resolve_tcp<S: Write + Read, T: ToQType>(
+    conn: &mut S,
+    name: &str,
+    deadline: Option<Instant>,
+) -> Result<Vec<T>, ResolveError> {
+    let (id, packet) = build_query::<T>(name)?;
+    let mut psize = (packet.len() as u16).to_be_bytes();
+
+    conn.write_all(&psize[..])?;
+    conn.write_all(&packet)?;
+
+    read_full(conn, &mut psize, deadline)?;
+    let psize = u16::from_be_bytes(psize) as usize;
+    let mut buf = vec![0u8; psize];
+    read_full(conn, &mut buf, deadline)?;
+
+    parse_response(id, name, &buf)
+}
+
+/// Send a query for `name` over a connected UDP socket.
+///
+/// Returns `Ok(None)` when the answer was truncated and the query has to be
+/// repeated over TCP with [`resolve`]. Datagrams answering other queries are
+/// skipped, the socket's read timeout bounds the whole wait.
+pub fn resolve_udp<T: ToQType>(
+    socket: &UdpSocket,
+    name: &str,
+) -> Result<Option<Vec<T>>, ResolveError> {
+    let timeout = socket.read_timeout()?;
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let result = recv_udp_answer(socket, name, deadline);
+    if timeout.is_some() {
+        let _ = socket.set_read_timeout(timeout);
+    }
+    result
+}
+
+fn recv_udp_answer<T: ToQType>(
+    socket: &UdpSocket,
+    name: &str,
+    deadline: Option<Instant>,
+) -> Result<Option<Vec<T>>, ResolveError> {
+    let (id, packet) = build_query::<T>(name)?;
+    socket.send(&packet)?;
+
+    // Without EDNS, servers truncate UDP answers to 512 bytes.
+    let mut buf = [0u8; 512];
+    loop {
+        // Shrink the read timeout so that skipped datagrams do not restart it.
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(ResolveError::TimedOut);
+            }
+            socket.set_read_timeout(Some(remaining))?;
+        }
+        let mut bufs = [socket::IovecRead {
+            buf: buf.as_mut_ptr(),
+            size: buf.len(),
+        }];
+        let (n, flags) = socket
+            .s
+            .recv_vectored(&mut bufs, socket::MsgFlags::empty())?;
+        match dns_parser::Header::parse(&buf[..n]) {
+            Ok(header) if header.id == id && !header.query => {
+                // A datagram cut off by the receive buffer is as incomplete
+                // as one the server truncated.
+                if header.truncated || flags.is_truncated() {
+                    return Ok(None);
+                }
+                match parse_response(id, name, &buf[..n]) {
+                    // A stale or spoofed reply, keep waiting for ours.
+                    Err(ResolveError::QuestionMismatch) => continue,
+                    r => return r.map(Some),
+                }
+            }
+            _ => continue,
+        }
+    }
+}
+
 /*
 Implement ToScoketAddrs using nslookup, so that DNS can be resolved in wasi.
 */
@@ -600,4 +1664,131 @@ impl<T: ToSocketAddrs + ?Sized> ToSocketAddrs for &T {
     fn to_socket_addrs(&self) -> io::Result<T::Iter> {
         (**self).to_socket_addrs()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CNAME: [u8; 16] = [0xc0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 4, 1, b'a', 0xc0, 12];
+    const A_FOR_CNAME: [u8; 18] = [1, b'a', 0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 1, 2, 3, 4];
+
+    /// A response to `query` with the QR and RA bits set and `answers` records.
+    fn response(query: &[u8], answers: u8) -> Vec<u8> {
+        let mut p = query.to_vec();
+        p[2] |= 0x80;
+        p[3] |= 0x80;
+        p[7] = answers;
+        p
+    }
+
+    struct OneByte<'a>(&'a [u8]);
+
+    impl Read for OneByte<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn parse_response_follows_cname() {
+        let (id, query) = build_query::<Ipv4Addr>("example.com").unwrap();
+        let mut p = response(&query, 2);
+        p.extend_from_slice(&CNAME);
+        p.extend_from_slice(&A_FOR_CNAME);
+        let r = parse_response::<Ipv4Addr>(id, "example.com", &p).unwrap();
+        assert_eq!(r, vec![Ipv4Addr::new(1, 2, 3, 4)]);
+    }
+
+    #[test]
+    fn parse_response_ignores_unrelated_records() {
+        let (id, query) = build_query::<Ipv4Addr>("example.com").unwrap();
+        let mut p = response(&query, 1);
+        p.extend_from_slice(&A_FOR_CNAME);
+        let r = parse_response::<Ipv4Addr>(id, "example.com", &p).unwrap();
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn parse_response_rejects_other_queries() {
+        let (id, query) = build_query::<Ipv4Addr>("example.com").unwrap();
+        let p = response(&query, 0);
+        assert!(matches!(
+            parse_response::<Ipv4Addr>(id.wrapping_add(1), "example.com", &p),
+            Err(ResolveError::IdMismatch)
+        ));
+        assert!(matches!(
+            parse_response::<Ipv4Addr>(id, "example.org", &p),
+            Err(ResolveError::QuestionMismatch)
+        ));
+        assert!(matches!(
+            parse_response::<Ipv6Addr>(id, "example.com", &p),
+            Err(ResolveError::QuestionMismatch)
+        ));
+        // The query itself is not an answer.
+        assert!(matches!(
+            parse_response::<Ipv4Addr>(id, "example.com", &query),
+            Err(ResolveError::IdMismatch)
+        ));
+    }
+
+    #[test]
+    fn parse_response_nodata() {
+        let (id, query) = build_query::<Ipv6Addr>("example.com").unwrap();
+        let p = response(&query, 0);
+        let r = parse_response::<Ipv6Addr>(id, "example.com", &p).unwrap();
+        assert!(r.is_empty());
+
+        // Without recursion, only an empty authority section is an error.
+        let mut p = query.clone();
+        p[2] |= 0x80;
+        assert!(matches!(
+            parse_response::<Ipv6Addr>(id, "example.com", &p),
+            Err(ResolveError::RecursionUnavailable)
+        ));
+        p[9] = 1;
+        p.extend_from_slice(&[0xc0, 12, 0, 2, 0, 1, 0, 0, 0, 60, 0, 2, 0xc0, 12]);
+        let r = parse_response::<Ipv6Addr>(id, "example.com", &p).unwrap();
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn parse_response_name_error() {
+        let (id, query) = build_query::<Ipv4Addr>("example.com").unwrap();
+        let mut p = response(&query, 0);
+        p[3] |= 3;
+        assert!(matches!(
+            parse_response::<Ipv4Addr>(id, "example.com", &p),
+            Err(ResolveError::Response(dns_parser::ResponseCode::NameError))
+        ));
+    }
+
+    #[test]
+    fn read_full_short_reads() {
+        let data = [1, 2, 3, 4, 5];
+        let mut buf = [0; 5];
+        read_full(&mut OneByte(&data), &mut buf, None).unwrap();
+        assert_eq!(buf, data);
+
+        let mut buf = [0; 6];
+        match read_full(&mut OneByte(&data), &mut buf, None) {
+            Err(ResolveError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn read_full_past_deadline() {
+        let mut buf = [0; 1];
+        let deadline = Instant::now() - Duration::from_secs(1);
+        assert!(matches!(
+            read_full(&mut OneByte(&[1]), &mut buf, Some(deadline)),
+            Err(ResolveError::TimedOut)
+        ));
+    }
+}
@@ -0,0 +1,515 @@
+use crate::{
+    nslookup_with_host_family, resolve_tcp, resolve_udp, socket, AddressPreference, ResolveError,
+    TcpStream, ToQType, UdpSocket,
+};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+/// How queries are sent to a nameserver.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Transport {
+    /// UDP, repeating the query over TCP when the answer is truncated.
+    #[default]
+    Udp,
+    /// TCP only.
+    Tcp,
+}
+
+/// Name resolution configuration used by [`nslookup`](crate::nslookup) and
+/// the [`ToSocketAddrs`](crate::ToSocketAddrs) impls.
+///
+/// A resolver without nameservers uses the host resolver, otherwise the
+/// nameservers are queried in order until one answers, `attempts` times.
+/// Names with fewer than `ndots` dots are tried with the search domains
+/// appended first, like in `resolv.conf(5)`.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use wasmedge_wasi_socket::{Resolver, TcpStream};
+///
+/// let resolver = Resolver::builder()
+///     .nameserver("1.1.1.1:53".parse().unwrap())
+///     .timeout(Duration::from_secs(2))
+///     .build();
+/// Resolver::set_default(resolver);
+///
+/// let stream = TcpStream::connect("example.com:80")?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    nameservers: Vec<SocketAddr>,
+    timeout: Duration,
+    attempts: usize,
+    transport: Transport,
+    preference: AddressPreference,
+    search: Vec<String>,
+    ndots: usize,
+    rotate: bool,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Resolver {
+            nameservers: vec![],
+            timeout: Duration::from_secs(5),
+            attempts: 2,
+            transport: Transport::Udp,
+            preference: AddressPreference::default(),
+            search: vec![],
+            ndots: 1,
+            rotate: false,
+        }
+    }
+}
+
+pub const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+static DEFAULT_RESOLVER: OnceLock<RwLock<Arc<Resolver>>> = OnceLock::new();
+
+/// [`RESOLV_CONF`] as read on first use.
+static SYSTEM_RESOLVER: OnceLock<Resolver> = OnceLock::new();
+
+/// First nameserver to try when `rotate` is set.
+static NEXT_NAMESERVER: AtomicUsize = AtomicUsize::new(0);
+
+fn default_resolver_lock() -> &'static RwLock<Arc<Resolver>> {
+    DEFAULT_RESOLVER.get_or_init(|| {
+        // Kept for compatibility, `Resolver::set_default` is preferred.
+        let resolver = match std::env::var("DNS_SERVER")
+            .ok()
+            .and_then(|s| s.parse().ok())
+        {
+            Some(server) => {
+                Resolver::system_with_nameservers(vec![server], AddressPreference::default())
+            }
+            None => Resolver::default(),
+        };
+        RwLock::new(Arc::new(resolver))
+    })
+}
+
+fn parse_resolv_conf(conf: &str) -> Resolver {
+    let mut resolver = Resolver::default();
+    for line in conf.lines() {
+        let line = line.split(['#', ';']).next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("nameserver") => {
+                // Scoped IPv6 addresses like `fe80::1%eth0` are not supported.
+                if let Some(ip) = words.next().and_then(|w| w.parse::<IpAddr>().ok()) {
+                    resolver.nameservers.push((ip, 53).into());
+                }
+            }
+            Some("domain") => {
+                resolver.search = words.next().map(str::to_string).into_iter().collect();
+            }
+            Some("search") => {
+                resolver.search = words.map(str::to_string).collect();
+            }
+            Some("options") => {
+                for option in words {
+                    let (name, value) = match option.split_once(':') {
+                        Some((name, value)) => (name, value.parse::<usize>().ok()),
+                        None => (option, None),
+                    };
+                    // Limits as in glibc.
+                    match (name, value) {
+                        ("ndots", Some(n)) => resolver.ndots = n.min(15),
+                        ("timeout", Some(n)) => {
+                            resolver.timeout = Duration::from_secs(n.clamp(1, 30) as u64)
+                        }
+                        ("attempts", Some(n)) => resolver.attempts = n.clamp(1, 5),
+                        ("rotate", _) => resolver.rotate = true,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    resolver
+}
+
+impl Resolver {
+    pub fn builder() -> ResolverBuilder {
+        ResolverBuilder::new()
+    }
+
+    /// Read the nameservers, search domains and the `ndots`, `timeout`,
+    /// `attempts` and `rotate` options from a `resolv.conf` file.
+    ///
+    /// Unknown and malformed lines are ignored. Without a `nameserver` line
+    /// the host resolver is used.
+    pub fn from_resolv_conf<P: AsRef<Path>>(path: P) -> io::Result<Resolver> {
+        std::fs::read_to_string(path).map(|conf| parse_resolv_conf(&conf))
+    }
+
+    /// The configuration in [`RESOLV_CONF`], or the host resolver when it
+    /// cannot be read, e.g. because `/etc` is not preopened.
+    ///
+    /// The file is read once, later changes are not picked up. The default
+    /// resolver only uses its nameservers as fallbacks for `DNS_SERVER`; use
+    /// `Resolver::set_default(Resolver::system())` to query them directly.
+    pub fn system() -> Resolver {
+        SYSTEM_RESOLVER
+            .get_or_init(|| Resolver::from_resolv_conf(RESOLV_CONF).unwrap_or_default())
+            .clone()
+    }
+
+    /// The system configuration with `nameservers` tried before the
+    /// configured ones.
+    pub(crate) fn system_with_nameservers(
+        mut nameservers: Vec<SocketAddr>,
+        preference: AddressPreference,
+    ) -> Resolver {
+        let system = Resolver::system();
+        for server in &system.nameservers {
+            if !nameservers.contains(server) {
+                nameservers.push(*server);
+            }
+        }
+        Resolver {
+            nameservers,
+            preference,
+            ..system
+        }
+    }
+
+    /// The process-wide resolver.
+    ///
+    /// Until [`Resolver::set_default`] is called this is the host resolver,
+    /// so `/etc/hosts` and the runtime's configuration apply. If the
+    /// `DNS_SERVER` environment variable is set, that nameserver is queried
+    /// instead, followed by those of [`Resolver::system`] whose options also
+    /// apply. The variable must be an `ip:port` address such as `8.8.8.8:53`,
+    /// other values are ignored.
+    pub fn default_resolver() -> Arc<Resolver> {
+        let lock = default_resolver_lock().read();
+        Arc::clone(&lock.unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Replace the process-wide resolver.
+    pub fn set_default(resolver: Resolver) {
+        let lock = default_resolver_lock().write();
+        *lock.unwrap_or_else(|e| e.into_inner()) = Arc::new(resolver);
+    }
+
+    pub fn nameservers(&self) -> &[SocketAddr] {
+        &self.nameservers
+    }
+
+    pub fn search_domains(&self) -> &[String] {
+        &self.search
+    }
+
+    /// Resolve `node`, with the port given by `service`.
+    ///
+    /// The host resolver also understands service names like `http`. When
+    /// querying nameservers only a numeric `service` is used, otherwise the
+    /// ports are 0.
+    pub fn lookup(&self, node: &str, service: &str) -> io::Result<Vec<SocketAddr>> {
+        let mut r = if self.nameservers.is_empty() {
+            let family = match self.preference {
+                AddressPreference::Ipv4Only => socket::AddressFamily::Inet4,
+                AddressPreference::Ipv6Only => socket::AddressFamily::Inet6,
+                _ => socket::AddressFamily::Unspec,
+            };
+            nslookup_with_host_family(node, service, family)?
+        } else {
+            let mut r = self.lookup_nameservers(node)?;
+            if let Ok(port) = service.parse::<u16>() {
+                r.iter_mut().for_each(|addr| addr.set_port(port));
+            }
+            r
+        };
+        self.preference.sort(&mut r);
+        if r.is_empty() {
+            // __WASI_ERRNO_AINODATA
+            Err(io::Error::from_raw_os_error(83))
+        } else {
+            Ok(r)
+        }
+    }
+
+    fn lookup_nameservers(&self, node: &str) -> io::Result<Vec<SocketAddr>> {
+        if node == "localhost" {
+            let mut r = vec![];
+            if self.preference.wants_ipv4() {
+                r.push((Ipv4Addr::LOCALHOST, 0).into());
+            }
+            if self.preference.wants_ipv6() {
+                r.push((Ipv6Addr::LOCALHOST, 0).into());
+            }
+            return Ok(r);
+        }
+
+        for name in self.candidates(node) {
+            let r = self.lookup_name(&name)?;
+            if !r.is_empty() {
+                return Ok(r);
+            }
+        }
+        Ok(vec![])
+    }
+
+    /// The names to query for `node`, in order, after search list expansion.
+    fn candidates(&self, node: &str) -> Vec<String> {
+        if let Some(absolute) = node.strip_suffix('.') {
+            return vec![absolute.to_string()];
+        }
+        let mut names: Vec<String> = self
+            .search
+            .iter()
+            .map(|domain| format!("{}.{}", node, domain.trim_end_matches('.')))
+            .collect();
+        if node.matches('.').count() >= self.ndots {
+            names.insert(0, node.to_string());
+        } else {
+            names.push(node.to_string());
+        }
+        names
+    }
+
+    /// Query the nameservers in turn until one of them answers for `name`.
+    /// A name that does not exist gives an empty result.
+    fn lookup_name(&self, name: &str) -> io::Result<Vec<SocketAddr>> {
+        let start = if self.rotate {
+            NEXT_NAMESERVER.fetch_add(1, Ordering::Relaxed)
+        } else {
+            0
+        };
+        let count = self.nameservers.len();
+        let mut last_error = ResolveError::TimedOut;
+        for _ in 0..self.attempts.max(1) {
+            for i in 0..count {
+                let server = self.nameservers[(start % count + i) % count];
+                match self.query_families(server, name) {
+                    Ok(r) => return Ok(r),
+                    // The name does not exist, other servers will agree.
+                    Err(ResolveError::Response(dns_parser::ResponseCode::NameError)) => {
+                        return Ok(vec![])
+                    }
+                    Err(e) => last_error = e,
+                }
+            }
+        }
+        Err(last_error.into())
+    }
+
+    /// Query every requested family, failing only if all of them fail.
+    fn query_families(
+        &self,
+        server: SocketAddr,
+        node: &str,
+    ) -> Result<Vec<SocketAddr>, ResolveError> {
+        let mut results = vec![];
+        if self.preference.wants_ipv4() {
+            results.push(self.query::<Ipv4Addr>(server, node).map(|v4| {
+                v4.into_iter()
+                    .map(|addr| SocketAddr::from((addr, 0)))
+                    .collect::<Vec<_>>()
+            }));
+        }
+        if self.preference.wants_ipv6() {
+            results.push(self.query::<Ipv6Addr>(server, node).map(|v6| {
+                v6.into_iter()
+                    .map(|addr| SocketAddr::from((addr, 0)))
+                    .collect::<Vec<_>>()
+            }));
+        }
+
+        let mut r = vec![];
+        let mut last_error = None;
+        let mut answered = false;
+        for result in results {
+            match result {
+                Ok(addrs) => {
+                    answered = true;
+                    r.extend(addrs);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match last_error {
+            Some(e) if !answered => Err(e),
+            _ => Ok(r),
+        }
+    }
+
+    fn query<T: ToQType>(&self, server: SocketAddr, name: &str) -> Result<Vec<T>, ResolveError> {
+        if self.transport == Transport::Udp {
+            let local: SocketAddr = match server {
+                SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+                SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+            };
+            let socket = UdpSocket::bind(local)?;
+            socket.connect(server)?;
+            socket.set_write_timeout(Some(self.timeout))?;
+            socket.set_read_timeout(Some(self.timeout))?;
+            if let Some(address) = resolve_udp::<T>(&socket, name)? {
+                return Ok(address);
+            }
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        let remaining = || {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                Err(ResolveError::TimedOut)
+            } else {
+                Ok(remaining)
+            }
+        };
+        let mut conn = TcpStream::connect_timeout(&server, remaining()?)?;
+        conn.set_write_timeout(Some(remaining()?))?;
+        conn.set_read_timeout(Some(remaining()?))?;
+        resolve_tcp::<_, T>(&mut conn, name, Some(deadline))
+    }
+}
+
+/// Builder for a [`Resolver`].
+///
+/// Defaults to the host resolver, and to a 5 second timeout and 2 attempts
+/// per nameserver once nameservers are added.
+#[derive(Debug, Clone, Default)]
+pub struct ResolverBuilder {
+    resolver: Resolver,
+}
+
+impl ResolverBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a nameserver, queried in the order they were added.
+    pub fn nameserver(mut self, nameserver: SocketAddr) -> Self {
+        self.resolver.nameservers.push(nameserver);
+        self
+    }
+
+    /// Timeout of a single query to one nameserver, at least a millisecond.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.resolver.timeout = timeout.max(Duration::from_millis(1));
+        self
+    }
+
+    /// How many times the list of nameservers is tried, at least once.
+    pub fn attempts(mut self, attempts: usize) -> Self {
+        self.resolver.attempts = attempts;
+        self
+    }
+
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.resolver.transport = transport;
+        self
+    }
+
+    pub fn preference(mut self, preference: AddressPreference) -> Self {
+        self.resolver.preference = preference;
+        self
+    }
+
+    /// Add a domain to the search list.
+    pub fn search_domain(mut self, domain: &str) -> Self {
+        self.resolver.search.push(domain.to_string());
+        self
+    }
+
+    /// Names with at least `ndots` dots are tried as is before the search
+    /// list is applied.
+    pub fn ndots(mut self, ndots: usize) -> Self {
+        self.resolver.ndots = ndots;
+        self
+    }
+
+    /// Spread queries over the nameservers instead of always starting with
+    /// the first one.
+    pub fn rotate(mut self, rotate: bool) -> Self {
+        self.resolver.rotate = rotate;
+        self
+    }
+
+    pub fn build(self) -> Resolver {
+        self.resolver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_resolv_conf_options() {
+        let resolver = parse_resolv_conf(
+            "# comment\n\
+             nameserver 10.0.0.1\n\
+             nameserver fe80::1%eth0\n\
+             nameserver 2001:db8::1 ; trailing comment\n\
+             domain a.com\n\
+             search b.com c.com\n\
+             options ndots:2 timeout:3 attempts:4 rotate unknown:1\n",
+        );
+        assert_eq!(
+            resolver.nameservers,
+            vec![
+                "10.0.0.1:53".parse::<SocketAddr>().unwrap(),
+                "[2001:db8::1]:53".parse().unwrap()
+            ]
+        );
+        assert_eq!(resolver.search, vec!["b.com", "c.com"]);
+        assert_eq!(resolver.ndots, 2);
+        assert_eq!(resolver.timeout, Duration::from_secs(3));
+        assert_eq!(resolver.attempts, 4);
+        assert!(resolver.rotate);
+    }
+
+    #[test]
+    fn parse_resolv_conf_limits() {
+        let resolver = parse_resolv_conf("options ndots:99 timeout:0 attempts:9");
+        assert_eq!(resolver.ndots, 15);
+        assert_eq!(resolver.timeout, Duration::from_secs(1));
+        assert_eq!(resolver.attempts, 5);
+
+        let resolver = parse_resolv_conf("options timeout:600 attempts:0 ndots:x");
+        assert_eq!(resolver.timeout, Duration::from_secs(30));
+        assert_eq!(resolver.attempts, 1);
+        assert_eq!(resolver.ndots, 1);
+    }
+
+    #[test]
+    fn parse_resolv_conf_last_search_wins() {
+        let resolver = parse_resolv_conf("search b.com c.com\ndomain a.com");
+        assert_eq!(resolver.search, vec!["a.com"]);
+        assert!(resolver.nameservers.is_empty());
+    }
+
+    #[test]
+    fn candidates_order() {
+        let resolver = Resolver::builder()
+            .search_domain("b.com")
+            .search_domain("c.com.")
+            .ndots(2)
+            .build();
+        assert_eq!(resolver.candidates("x"), vec!["x.b.com", "x.c.com", "x"]);
+        assert_eq!(
+            resolver.candidates("x.y"),
+            vec!["x.y.b.com", "x.y.c.com", "x.y"]
+        );
+        assert_eq!(
+            resolver.candidates("x.y.z"),
+            vec!["x.y.z", "x.y.z.b.com", "x.y.z.c.com"]
+        );
+        assert_eq!(resolver.candidates("x.y."), vec!["x.y"]);
+    }
+
+    #[test]
+    fn builder_clamps_zero_timeout() {
+        let resolver = Resolver::builder().timeout(Duration::ZERO).build();
+        assert!(!resolver.timeout.is_zero());
+    }
+}
@@ -1,8 +1,8 @@
 use core::ffi;
 use std::io;
 use std::mem::MaybeUninit;
-use std::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, SocketAddrV4, SocketAddrV6};
-use std::os::wasi::prelude::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::net::{IpAddr, Shutdown, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::os::wasi::prelude::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
 
 #[derive(Copy, Clone, Debug)]
 #[repr(u8, align(1))]
@@ -54,6 +54,80 @@ pub struct WasiAddress {
 
 unsafe impl Send for WasiAddress {}
 
+/// Storage for the bytes a [`WasiAddress`] points to in the legacy
+/// `sock_send_to` and `sock_recv_from` calls.
+///
+/// Outgoing addresses are the bare IP octets, the port is passed separately.
+/// `sock_recv_from` writes the address family as a little-endian `u16`
+/// followed by the IP octets.
+#[derive(Clone)]
+pub struct WasiAddressBuf {
+    buf: [u8; 128],
+    size: usize,
+}
+
+impl WasiAddressBuf {
+    /// Create a zeroed buffer for `sock_recv_from` to write into.
+    pub fn new() -> Self {
+        WasiAddressBuf {
+            buf: [0; 128],
+            size: 128,
+        }
+    }
+
+    pub fn as_wasi_address(&mut self) -> WasiAddress {
+        WasiAddress {
+            buf: self.buf.as_mut_ptr(),
+            size: self.size,
+        }
+    }
+
+    /// Decode the address written by `sock_recv_from`, with the port it
+    /// returned alongside.
+    ///
+    /// An unknown address family or out of range port is reported as an
+    /// error, as both come straight from the runtime.
+    pub fn to_socket_addr(&self, port: u32) -> io::Result<SocketAddr> {
+        let port = u16::try_from(port)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid port value"))?;
+        let family = u16::from_le_bytes([self.buf[0], self.buf[1]]);
+        if family == AddressFamily::Inet4 as u16 {
+            let mut octets = [0u8; 4];
+            octets.copy_from_slice(&self.buf[2..6]);
+            Ok(SocketAddr::V4(SocketAddrV4::new(octets.into(), port)))
+        } else if family == AddressFamily::Inet6 as u16 {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&self.buf[2..18]);
+            Ok(SocketAddr::V6(SocketAddrV6::new(octets.into(), port, 0, 0)))
+        } else {
+            Err(io::Error::from_raw_os_error(libc::EAFNOSUPPORT))
+        }
+    }
+}
+
+impl Default for WasiAddressBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<IpAddr> for WasiAddressBuf {
+    fn from(ip: IpAddr) -> Self {
+        let mut buf = [0; 128];
+        let size = match ip {
+            IpAddr::V4(ip) => {
+                buf[..4].copy_from_slice(&ip.octets());
+                4
+            }
+            IpAddr::V6(ip) => {
+                buf[..16].copy_from_slice(&ip.octets());
+                16
+            }
+        };
+        WasiAddressBuf { buf, size }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 #[repr(u16, align(2))]
 pub enum AiFlags {
@@ -117,11 +191,13 @@ pub struct WasiAddrinfo {
 }
 
 impl WasiAddrinfo {
+    /// Hints that do not restrict the results, as the host resolver returned
+    /// them before hints were passed on.
     pub fn default() -> WasiAddrinfo {
         WasiAddrinfo {
             ai_flags: AiFlags::AiPassive,
-            ai_family: AddressFamily::Inet4,
-            ai_socktype: SocketType::Stream,
+            ai_family: AddressFamily::Unspec,
+            ai_socktype: SocketType::Any,
             ai_protocol: AiProtocol::IPProtoTCP,
             ai_addr: std::ptr::null_mut(),
             ai_addrlen: 0,
@@ -134,6 +210,8 @@ impl WasiAddrinfo {
     /// Get Address Information
     ///
     /// As calling FFI, use buffer as parameter in order to avoid memory leak.
+    /// The `ai_family` and `ai_socktype` of `hints` are passed to the host
+    /// resolver, use `AddressFamily::Unspec` to get both IPv4 and IPv6 results.
     /// TODO: make it actually return the same amount of info as getaddinfo in C
     pub fn get_addrinfo(
         node: &str,
@@ -167,7 +245,7 @@ impl WasiAddrinfo {
         let mut wamr_hints = socket_wamr::WasiAddrInfoHints {
             type_: socket_type,
             family: address_family,
-            hints_enabled: 0,
+            hints_enabled: 1,
         };
         let mut addr_info_array: Vec<socket_wamr::WasiAddrInfo> =
             vec![socket_wamr::WasiAddrInfo::default(); max_reslen];
@@ -184,6 +262,8 @@ impl WasiAddrinfo {
         if errno != 0 {
             return Err(io::Error::from_raw_os_error(errno.into()));
         }
+        // `max_info_size` is the total number of results, which may exceed the buffer.
+        addr_info_array.truncate(max_info_size as usize);
         return Ok(addr_info_array);
     }
 }
@@ -286,6 +366,81 @@ pub const MSG_WAITALL: u16 = 2; // __WASI_RIFLAGS_RECV_WAITALL
 
 pub const MSG_TRUNC: u16 = 1; // __WASI_ROFLAGS_RECV_DATA_TRUNCATED
 
+/// Input flags for the `recv*` calls (`riflags`).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MsgFlags(u16);
+
+impl MsgFlags {
+    /// Return the data without removing it from the queue.
+    pub const PEEK: MsgFlags = MsgFlags(MSG_PEEK);
+    /// Block until the whole buffer is filled, on stream sockets.
+    pub const WAITALL: MsgFlags = MsgFlags(MSG_WAITALL);
+
+    pub const fn empty() -> Self {
+        MsgFlags(0)
+    }
+
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub const fn contains(&self, other: MsgFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for MsgFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        MsgFlags(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for MsgFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Input flags for the `send*` calls (`siflags`).
+///
+/// WASI does not define any send flags yet, so only the empty set exists;
+/// `MSG_NOSIGNAL` is implied as there are no signals, and out-of-band data is
+/// not supported.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SendFlags(u16);
+
+impl SendFlags {
+    pub const fn empty() -> Self {
+        SendFlags(0)
+    }
+
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+}
+
+/// Flags reported by the `recv*` calls (`roflags`).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RecvFlags(u16);
+
+impl RecvFlags {
+    pub const fn from_bits(bits: u16) -> Self {
+        RecvFlags(bits)
+    }
+
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// Whether the datagram was larger than the buffer and the rest of it
+    /// was discarded.
+    pub const fn is_truncated(&self) -> bool {
+        self.0 & MSG_TRUNC != 0
+    }
+}
+
 macro_rules! syscall {
     ($fn: ident ( $($arg: expr),* $(,)* ) ) => {{
         #[allow(unused_unsafe)]
@@ -326,12 +481,11 @@ fn fcntl_remove(fd: RawFd, get_cmd: i32, set_cmd: i32, flag: i32) -> io::Result<
 }
 
 mod wasi_sock {
-    use super::{IovecRead, IovecWrite, WasiAddress};
+    use super::{IovecRead, IovecWrite};
 
     #[link(wasm_import_module = "wasi_snapshot_preview1")]
     extern "C" {
         // pub fn sock_open(addr_family: u8, sock_type: u8, fd: *mut u32) -> u32;
-        pub fn sock_bind(fd: u32, addr: *mut WasiAddress, port: u32) -> u32;
         pub fn sock_listen(fd: u32, backlog: u32) -> u32;
         pub fn sock_accept(fd: u32, fd: *mut u32) -> u32;
         // pub fn sock_connect(fd: u32, addr: *mut WasiAddress, port: u32) -> u32;
@@ -402,27 +556,126 @@ mod wasi_sock {
     }
 }
 
+/// A socket handle.
+///
+/// Handles created with [`Socket::try_clone`] share the descriptor, which is
+/// shut down and closed when the last of them is dropped.
 #[derive(Debug)]
 pub struct Socket {
     fd: RawFd,
+    owner: Arc<OwnedSocketFd>,
+}
+
+#[derive(Debug)]
+struct OwnedSocketFd(RawFd);
+
+impl Drop for OwnedSocketFd {
+    fn drop(&mut self) {
+        let _ = shutdown_fd(self.0, Shutdown::Both);
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+fn shutdown_fd(fd: RawFd, how: Shutdown) -> io::Result<()> {
+    let flags = match how {
+        Shutdown::Read => 1,
+        Shutdown::Write => 2,
+        Shutdown::Both => 3,
+    };
+    let res = unsafe { sock_shutdown(fd as u32, flags) };
+    if res == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::from_raw_os_error(res as i32))
+    }
 }
 
+use std::sync::Arc;
 use std::time::Duration;
 use wasi_sock::*;
 
-use crate::socket_wamr::{self, wamr_sock_bind, WasiAddrIp4, WasiAddrIp4Port, WasiAddrType};
+use crate::socket_wamr::{self, wamr_sock_bind, WasiAddrType};
 
-fn from_timeval(duration: libc::timeval) -> Option<Duration> {
-    if duration.tv_sec == 0 && duration.tv_usec == 0 {
+fn from_micros(timeout_us: u64) -> Option<Duration> {
+    if timeout_us == 0 {
         None
     } else {
-        let sec = duration.tv_sec as u64;
-        let nsec = (duration.tv_usec as u32) * 1000;
-        Some(Duration::new(sec, nsec))
+        Some(Duration::from_micros(timeout_us))
+    }
+}
+
+pub(crate) fn as_uninit_mut(buf: &mut [u8]) -> &mut [MaybeUninit<u8>] {
+    // SAFETY: `MaybeUninit<u8>` has the same layout as `u8`, and only
+    // initialized bytes are written through the returned slice.
+    unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) }
+}
+
+/// Timeouts are passed in microseconds, with 0 meaning no timeout. Partial
+/// microseconds are rounded up, so that short timeouts do not become 0.
+fn timeout_micros(duration: Option<Duration>) -> u64 {
+    match duration {
+        Some(d) if !d.is_zero() => {
+            let micros = d.as_nanos().div_ceil(1000);
+            micros.min(u64::MAX as u128) as u64
+        }
+        _ => 0,
+    }
+}
+
+/// The runtime works in whole seconds for linger and keepalive times.
+/// Partial seconds are rounded up, so that short durations do not become 0.
+fn secs_u32(duration: Duration) -> u32 {
+    let secs = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+    secs.min(u32::MAX as u64) as u32
+}
+
+fn wamr_result<T>(errno: socket_wamr::WasiErrno, value: T) -> io::Result<T> {
+    if errno == 0 {
+        Ok(value)
+    } else {
+        Err(io::Error::from_raw_os_error(errno as i32))
     }
 }
 
 impl Socket {
+    fn owned(fd: RawFd) -> Self {
+        Socket {
+            fd,
+            owner: Arc::new(OwnedSocketFd(fd)),
+        }
+    }
+
+    /// Create a new handle to the same socket.
+    ///
+    /// Both handles share the descriptor, its options and its nonblocking
+    /// mode. The socket is only shut down and closed once every handle is
+    /// dropped.
+    pub fn try_clone(&self) -> io::Result<Socket> {
+        Ok(self.clone_handle())
+    }
+
+    pub(crate) fn clone_handle(&self) -> Socket {
+        Socket {
+            fd: self.fd,
+            owner: Arc::clone(&self.owner),
+        }
+    }
+
+    /// Release the descriptor without closing it, if this is the only handle
+    /// to it. Otherwise the socket is given back.
+    pub fn try_into_raw_fd(self) -> Result<RawFd, Socket> {
+        let Socket { fd, owner } = self;
+        match Arc::try_unwrap(owner) {
+            Ok(owned) => {
+                std::mem::forget(owned);
+                Ok(fd)
+            }
+            Err(owner) => Err(Socket { fd, owner }),
+        }
+    }
+
     pub fn new(addr_family: AddressFamily, sock_kind: SocketType) -> io::Result<Self> {
         let mut socket_fd: socket_wamr::WasiFd = 0;
         let poolfd = u32::MAX;
@@ -444,9 +697,7 @@ impl Socket {
             &mut socket_fd,
         );
         if errno == 0 {
-            Ok(Socket {
-                fd: socket_fd as i32,
-            })
+            Ok(Socket::owned(socket_fd as i32))
         } else {
             Err(io::Error::from_raw_os_error(errno as i32))
         }
@@ -502,67 +753,25 @@ impl Socket {
     }
 
     pub fn set_send_timeout(&self, duration: Option<Duration>) -> io::Result<()> {
-        let duration_micros = match duration {
-            Some(dur) => dur.as_micros(),
-            None => 0,
-        };
-        self.setsockopt_socket(socket_wamr::SocketOptName::SoSndtimeo(
-            duration_micros as u64,
-        ))
+        let micros = timeout_micros(duration);
+        self.setsockopt_socket(socket_wamr::SocketOptName::SoSndtimeo(micros))
     }
 
     pub fn get_send_timeout(&self) -> io::Result<Option<Duration>> {
-        unsafe {
-            let fd = self.fd;
-            let mut payload: MaybeUninit<libc::timeval> = MaybeUninit::uninit();
-            let mut len = std::mem::size_of::<libc::timeval>() as u32;
-
-            let e = sock_getsockopt(
-                fd as u32,
-                SocketOptLevel::SolSocket as i32,
-                SocketOptName::SoSndtimeo as i32,
-                payload.as_mut_ptr().cast(),
-                &mut len,
-            );
-
-            if e == 0 {
-                Ok(from_timeval(payload.assume_init()))
-            } else {
-                Err(io::Error::from_raw_os_error(e as i32))
-            }
-        }
+        let mut timeout_us: u64 = 0;
+        let errno = socket_wamr::wamr_sock_get_send_timeout(self.fd as u32, &mut timeout_us);
+        wamr_result(errno, from_micros(timeout_us))
     }
 
     pub fn set_recv_timeout(&self, duration: Option<std::time::Duration>) -> io::Result<()> {
-        let duration_micros = match duration {
-            Some(dur) => dur.as_micros(),
-            None => 0,
-        };
-        self.setsockopt_socket(socket_wamr::SocketOptName::SoRcvtimeo(
-            duration_micros as u64,
-        ))
+        let micros = timeout_micros(duration);
+        self.setsockopt_socket(socket_wamr::SocketOptName::SoRcvtimeo(micros))
     }
 
     pub fn get_recv_timeout(&self) -> io::Result<Option<Duration>> {
-        unsafe {
-            let fd = self.fd;
-            let mut payload: MaybeUninit<libc::timeval> = MaybeUninit::uninit();
-            let mut len = std::mem::size_of::<libc::timeval>() as u32;
-
-            let e = sock_getsockopt(
-                fd as u32,
-                SocketOptLevel::SolSocket as i32,
-                SocketOptName::SoRcvtimeo as i32,
-                payload.as_mut_ptr().cast(),
-                &mut len,
-            );
-
-            if e == 0 {
-                Ok(from_timeval(payload.assume_init()))
-            } else {
-                Err(io::Error::from_raw_os_error(e as i32))
-            }
-        }
+        let mut timeout_us: u64 = 0;
+        let errno = socket_wamr::wamr_sock_get_recv_timeout(self.fd as u32, &mut timeout_us);
+        wamr_result(errno, from_micros(timeout_us))
     }
 
     pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
@@ -581,7 +790,7 @@ impl Socket {
         }
     }
 
-    pub fn send_vectored(&self, bufs: &[io::IoSlice<'_>], flags: u16) -> io::Result<usize> {
+    pub fn send_vectored(&self, bufs: &[io::IoSlice<'_>], flags: SendFlags) -> io::Result<usize> {
         unsafe {
             let mut send_len: u32 = 0;
 
@@ -597,7 +806,7 @@ impl Socket {
                 self.as_raw_fd() as u32,
                 write_bufs.as_ptr(),
                 write_bufs.len() as u32,
-                flags,
+                flags.bits(),
                 &mut send_len,
             );
             if res == 0 {
@@ -610,14 +819,8 @@ impl Socket {
 
     pub fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
         let port = addr.port() as u32;
-        let vaddr = match addr {
-            SocketAddr::V4(ipv4) => ipv4.ip().octets().to_vec(),
-            SocketAddr::V6(ipv6) => ipv6.ip().octets().to_vec(),
-        };
-        let addr = WasiAddress {
-            buf: vaddr.as_ptr(),
-            size: vaddr.len(),
-        };
+        let mut vaddr = WasiAddressBuf::from(addr.ip());
+        let addr = vaddr.as_wasi_address();
 
         let vec = IovecWrite {
             buf: buf.as_ptr(),
@@ -648,17 +851,11 @@ impl Socket {
         &self,
         bufs: &[io::IoSlice<'_>],
         addr: SocketAddr,
-        flags: u16,
+        flags: SendFlags,
     ) -> io::Result<usize> {
         let port = addr.port() as u32;
-        let vaddr = match addr {
-            SocketAddr::V4(ipv4) => ipv4.ip().octets().to_vec(),
-            SocketAddr::V6(ipv6) => ipv6.ip().octets().to_vec(),
-        };
-        let addr = WasiAddress {
-            buf: vaddr.as_ptr(),
-            size: vaddr.len(),
-        };
+        let mut vaddr = WasiAddressBuf::from(addr.ip());
+        let addr = vaddr.as_wasi_address();
 
         let mut write_bufs = Vec::with_capacity(bufs.len());
         for b in bufs {
@@ -676,7 +873,7 @@ impl Socket {
                 write_bufs.len() as u32,
                 &addr as *const WasiAddress as *const u8,
                 port,
-                flags,
+                flags.bits(),
                 &mut send_len,
             );
             if res == 0 {
@@ -716,8 +913,8 @@ impl Socket {
     pub fn recv_with_flags(
         &self,
         buf: &mut [MaybeUninit<u8>],
-        flags: u16,
-    ) -> io::Result<(usize, usize)> {
+        flags: MsgFlags,
+    ) -> io::Result<(usize, RecvFlags)> {
         let mut recv_len: usize = 0;
         let mut oflags: usize = 0;
         let mut vec = IovecRead {
@@ -730,19 +927,29 @@ impl Socket {
                 self.as_raw_fd() as u32,
                 &mut vec,
                 1,
-                flags,
+                flags.bits(),
                 &mut recv_len,
                 &mut oflags,
             );
             if res == 0 {
-                Ok((recv_len, oflags))
+                Ok((recv_len, RecvFlags::from_bits(oflags as u16)))
             } else {
                 Err(io::Error::from_raw_os_error(res as i32))
             }
         }
     }
 
-    pub fn recv_vectored(&self, bufs: &mut [IovecRead], flags: u16) -> io::Result<(usize, usize)> {
+    /// Receive data without removing it from the queue (`MSG_PEEK`).
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let (n, _) = self.recv_with_flags(as_uninit_mut(buf), MsgFlags::PEEK)?;
+        Ok(n)
+    }
+
+    pub fn recv_vectored(
+        &self,
+        bufs: &mut [IovecRead],
+        flags: MsgFlags,
+    ) -> io::Result<(usize, RecvFlags)> {
         let mut recv_len: usize = 0;
         let mut oflags: usize = 0;
 
@@ -751,12 +958,12 @@ impl Socket {
                 self.as_raw_fd() as u32,
                 bufs.as_mut_ptr(),
                 bufs.len(),
-                flags,
+                flags.bits(),
                 &mut recv_len,
                 &mut oflags,
             );
             if res == 0 {
-                Ok((recv_len, oflags))
+                Ok((recv_len, RecvFlags::from_bits(oflags as u16)))
             } else {
                 Err(io::Error::from_raw_os_error(res as i32))
             }
@@ -765,12 +972,8 @@ impl Socket {
 
     pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
         let flags = 0;
-        let addr_buf = [0; 128];
-
-        let mut addr = WasiAddress {
-            buf: addr_buf.as_ptr(),
-            size: 128,
-        };
+        let mut addr_buf = WasiAddressBuf::new();
+        let mut addr = addr_buf.as_wasi_address();
 
         let mut recv_buf = IovecRead {
             buf: buf.as_mut_ptr(),
@@ -792,22 +995,7 @@ impl Socket {
                 &mut oflags,
             );
             if res == 0 {
-                let sin_family = {
-                    let mut d = [0, 0];
-                    d.clone_from_slice(&addr_buf[0..2]);
-                    u16::from_le_bytes(d) as u8
-                };
-                let sin_addr = if sin_family == AddressFamily::Inet4 as u8 {
-                    let ip_addr = Ipv4Addr::new(addr_buf[2], addr_buf[3], addr_buf[4], addr_buf[5]);
-                    SocketAddr::V4(SocketAddrV4::new(ip_addr, sin_port as u16))
-                } else if sin_family == AddressFamily::Inet6 as u8 {
-                    let mut ipv6_addr = [0u8; 16];
-                    ipv6_addr.copy_from_slice(&addr_buf[2..18]);
-                    let ip_addr = Ipv6Addr::from(ipv6_addr);
-                    SocketAddr::V6(SocketAddrV6::new(ip_addr, sin_port as u16, 0, 0))
-                } else {
-                    unimplemented!("Address family not supported by protocol");
-                };
+                let sin_addr = addr_buf.to_socket_addr(sin_port)?;
 
                 Ok((recv_len, sin_addr))
             } else {
@@ -819,14 +1007,10 @@ impl Socket {
     pub fn recv_from_with_flags(
         &self,
         buf: &mut [MaybeUninit<u8>],
-        flags: u16,
-    ) -> io::Result<(usize, SocketAddr, usize)> {
-        let addr_buf = [0; 128];
-
-        let mut addr = WasiAddress {
-            buf: addr_buf.as_ptr(),
-            size: 128,
-        };
+        flags: MsgFlags,
+    ) -> io::Result<(usize, SocketAddr, RecvFlags)> {
+        let mut addr_buf = WasiAddressBuf::new();
+        let mut addr = addr_buf.as_wasi_address();
 
         let mut recv_buf = IovecRead {
             buf: buf.as_mut_ptr().cast(),
@@ -842,47 +1026,34 @@ impl Socket {
                 &mut recv_buf,
                 1,
                 &mut addr as *mut WasiAddress as *mut u8,
-                flags,
+                flags.bits(),
                 &mut sin_port,
                 &mut recv_len,
                 &mut oflags,
             );
             if res == 0 {
-                let sin_family = {
-                    let mut d = [0, 0];
-                    d.clone_from_slice(&addr_buf[0..2]);
-                    u16::from_le_bytes(d) as u8
-                };
-                let sin_addr = if sin_family == AddressFamily::Inet4 as u8 {
-                    let ip_addr = Ipv4Addr::new(addr_buf[2], addr_buf[3], addr_buf[4], addr_buf[5]);
-                    SocketAddr::V4(SocketAddrV4::new(ip_addr, sin_port as u16))
-                } else if sin_family == AddressFamily::Inet6 as u8 {
-                    let mut ipv6_addr = [0u8; 16];
-                    ipv6_addr.copy_from_slice(&addr_buf[2..18]);
-                    let ip_addr = Ipv6Addr::from(ipv6_addr);
-                    SocketAddr::V6(SocketAddrV6::new(ip_addr, sin_port as u16, 0, 0))
-                } else {
-                    unimplemented!("Address family not supported by protocol");
-                };
-
-                Ok((recv_len, sin_addr, oflags))
+                let sin_addr = addr_buf.to_socket_addr(sin_port)?;
+
+                Ok((recv_len, sin_addr, RecvFlags::from_bits(oflags as u16)))
             } else {
                 Err(io::Error::from_raw_os_error(res as i32))
             }
         }
     }
 
+    /// Receive a datagram without removing it from the queue (`MSG_PEEK`).
+    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (n, addr, _) = self.recv_from_with_flags(as_uninit_mut(buf), MsgFlags::PEEK)?;
+        Ok((n, addr))
+    }
+
     pub fn recv_from_vectored(
         &self,
         bufs: &mut [IovecRead],
-        flags: u16,
-    ) -> io::Result<(usize, SocketAddr, usize)> {
-        let addr_buf = [0; 128];
-
-        let mut addr = WasiAddress {
-            buf: addr_buf.as_ptr(),
-            size: 128,
-        };
+        flags: MsgFlags,
+    ) -> io::Result<(usize, SocketAddr, RecvFlags)> {
+        let mut addr_buf = WasiAddressBuf::new();
+        let mut addr = addr_buf.as_wasi_address();
 
         let mut recv_len: usize = 0;
         let mut oflags: usize = 0;
@@ -891,32 +1062,17 @@ impl Socket {
             let res = sock_recv_from(
                 self.as_raw_fd() as u32,
                 bufs.as_mut_ptr(),
-                1,
+                bufs.len() as u32,
                 &mut addr as *mut WasiAddress as *mut u8,
-                flags,
+                flags.bits(),
                 &mut sin_port,
                 &mut recv_len,
                 &mut oflags,
             );
             if res == 0 {
-                let sin_family = {
-                    let mut d = [0, 0];
-                    d.clone_from_slice(&addr_buf[0..2]);
-                    u16::from_le_bytes(d) as u8
-                };
-                let sin_addr = if sin_family == AddressFamily::Inet4 as u8 {
-                    let ip_addr = Ipv4Addr::new(addr_buf[2], addr_buf[3], addr_buf[4], addr_buf[5]);
-                    SocketAddr::V4(SocketAddrV4::new(ip_addr, sin_port as u16))
-                } else if sin_family == AddressFamily::Inet6 as u8 {
-                    let mut ipv6_addr = [0u8; 16];
-                    ipv6_addr.copy_from_slice(&addr_buf[2..18]);
-                    let ip_addr = Ipv6Addr::from(ipv6_addr);
-                    SocketAddr::V6(SocketAddrV6::new(ip_addr, sin_port as u16, 0, 0))
-                } else {
-                    unimplemented!("Address family not supported by protocol");
-                };
-
-                Ok((recv_len, sin_addr, oflags))
+                let sin_addr = addr_buf.to_socket_addr(sin_port)?;
+
+                Ok((recv_len, sin_addr, RecvFlags::from_bits(oflags as u16)))
             } else {
                 Err(io::Error::from_raw_os_error(res as i32))
             }
@@ -940,28 +1096,7 @@ impl Socket {
 
     pub fn connect(&self, addrs: &SocketAddr) -> io::Result<()> {
         let fd: u32 = self.as_raw_fd() as u32;
-        let vaddr;
-        let port;
-        if let SocketAddr::V4(addrs) = addrs {
-            vaddr = addrs.ip().octets();
-            port = addrs.port();
-        } else {
-            return Err(io::Error::from(io::ErrorKind::Unsupported));
-        }
-        let wasi_addr = socket_wamr::WasiAddr {
-            kind: socket_wamr::WasiAddrType::IPv4,
-            addr: socket_wamr::WasiAddrUnion {
-                ip4: WasiAddrIp4Port {
-                    addr: WasiAddrIp4 {
-                        n0: vaddr[0],
-                        n1: vaddr[1],
-                        n2: vaddr[2],
-                        n3: vaddr[3],
-                    },
-                    port: port,
-                },
-            },
-        };
+        let wasi_addr = socket_wamr::WasiAddr::from(*addrs);
         let errno = socket_wamr::wamr_sock_connect(fd, &wasi_addr as *const _);
         if errno != 0 {
             Err(io::Error::from_raw_os_error(errno as i32))
@@ -970,37 +1105,15 @@ impl Socket {
         }
     }
 
+    /// Bind to `addrs`.
+    ///
+    /// To accept both IPv4-mapped and native IPv6 peers on `[::]`, call
+    /// `set_ipv6_only(false)` before binding.
     pub fn bind(&self, addrs: &SocketAddr) -> io::Result<()> {
-        unsafe {
-            let fd = self.as_raw_fd();
-            let mut vaddr: [u8; 16] = [0; 16];
-            let port;
-            let size;
-            match addrs {
-                SocketAddr::V4(addr) => {
-                    let ip = addr.ip().octets();
-                    (&mut vaddr[0..4]).clone_from_slice(&ip);
-                    port = addr.port();
-                    size = 4;
-                }
-                SocketAddr::V6(addr) => {
-                    let ip = addr.ip().octets();
-                    vaddr.clone_from_slice(&ip);
-                    port = addr.port();
-                    size = 16;
-                }
-            }
-            let mut addr = WasiAddress {
-                buf: vaddr.as_ptr(),
-                size,
-            };
-            let res = sock_bind(fd as u32, &mut addr, port as u32);
-            if res != 0 {
-                Err(io::Error::from_raw_os_error(res as i32))
-            } else {
-                Ok(())
-            }
-        }
+        let fd: u32 = self.as_raw_fd() as u32;
+        let wasi_addr = socket_wamr::WasiAddr::from(*addrs);
+        let errno = wamr_sock_bind(fd, &wasi_addr);
+        wamr_result(errno, ())
     }
 
     pub fn listen(&self, backlog: i32) -> io::Result<()> {
@@ -1022,7 +1135,7 @@ impl Socket {
             if res != 0 {
                 Err(io::Error::from_raw_os_error(res as i32))
             } else {
-                let s = Socket { fd: fd as i32 };
+                let s = Socket::owned(fd as i32);
                 s.set_nonblocking(nonblocking)?;
                 Ok(s)
             }
@@ -1037,21 +1150,7 @@ impl Socket {
         if errno != 0 {
             Err(io::Error::from_raw_os_error(errno as i32))
         } else {
-            if wasi_addr.kind == socket_wamr::WasiAddrType::IPv4 {
-                let addr = unsafe { wasi_addr.addr.ip4.addr };
-                let ip_addr = Ipv4Addr::new(addr.n0, addr.n1, addr.n2, addr.n3);
-                let port = unsafe { wasi_addr.addr.ip4.port };
-                Ok(SocketAddr::V4(SocketAddrV4::new(ip_addr, port)))
-            } else if wasi_addr.kind == socket_wamr::WasiAddrType::IPv6 {
-                let addr = unsafe { wasi_addr.addr.ip6.addr };
-                let port = unsafe { wasi_addr.addr.ip6.port };
-                let ip_addr = Ipv6Addr::new(
-                    addr.n0, addr.n1, addr.n2, addr.n3, addr.h0, addr.h1, addr.h2, addr.h3,
-                );
-                Ok(SocketAddr::V6(SocketAddrV6::new(ip_addr, port, 0, 0)))
-            } else {
-                Err(io::Error::from(io::ErrorKind::Unsupported))
-            }
+            Ok(SocketAddr::from(&wasi_addr))
         }
     }
 
@@ -1063,92 +1162,57 @@ impl Socket {
         if errno != 0 {
             Err(io::Error::from_raw_os_error(errno as i32))
         } else {
-            if wasi_addr.kind == socket_wamr::WasiAddrType::IPv4 {
-                let addr = unsafe { wasi_addr.addr.ip4.addr };
-                let ip_addr = Ipv4Addr::new(addr.n0, addr.n1, addr.n2, addr.n3);
-                let port = unsafe { wasi_addr.addr.ip4.port };
-                Ok(SocketAddr::V4(SocketAddrV4::new(ip_addr, port)))
-            } else if wasi_addr.kind == socket_wamr::WasiAddrType::IPv6 {
-                let addr = unsafe { wasi_addr.addr.ip6.addr };
-                let port = unsafe { wasi_addr.addr.ip6.port };
-                let ip_addr = Ipv6Addr::new(
-                    addr.n0, addr.n1, addr.n2, addr.n3, addr.h0, addr.h1, addr.h2, addr.h3,
-                );
-                Ok(SocketAddr::V6(SocketAddrV6::new(ip_addr, port, 0, 0)))
-            } else {
-                Err(io::Error::from(io::ErrorKind::Unsupported))
-            }
+            Ok(SocketAddr::from(&wasi_addr))
         }
     }
 
-    pub fn take_error(&self) -> io::Result<()> {
-        Ok(())
-        // unsafe {
-        //     let fd = self.fd;
-        //     let mut error = 0;
-        //     let mut len = std::mem::size_of::<i32>() as u32;
-        //     let res = sock_getsockopt(
-        //         fd as u32,
-        //         SocketOptLevel::SolSocket as i32,
-        //         SocketOptName::SoError as i32,
-        //         &mut error,
-        //         &mut len,
-        //     );
-        //     if res == 0 && error == 0 {
-        //         Ok(())
-        //     } else if res == 0 && error != 0 {
-        //         Err(io::Error::from_raw_os_error(error))
-        //     } else {
-        //         Err(io::Error::from_raw_os_error(res as i32))
-        //     }
-        // }
-    }
-
-    pub fn is_listener(&self) -> io::Result<bool> {
+    /// Get and clear the pending socket error (`SO_ERROR`).
+    ///
+    /// The WAMR socket extension has no getter for this option, so it goes
+    /// through the legacy `sock_getsockopt`, which not every runtime provides.
+    /// Use [`Socket::connect_result`] to check a nonblocking connect.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
         unsafe {
             let fd = self.fd;
-            let mut val = 0;
+            let mut error = 0;
             let mut len = std::mem::size_of::<i32>() as u32;
             let res = sock_getsockopt(
                 fd as u32,
                 SocketOptLevel::SolSocket as i32,
-                SocketOptName::SoAcceptconn as i32,
-                &mut val,
+                SocketOptName::SoError as i32,
+                &mut error,
                 &mut len,
             );
             if res != 0 {
                 Err(io::Error::from_raw_os_error(res as i32))
+            } else if error == 0 {
+                Ok(None)
             } else {
-                Ok(val != 0)
+                Ok(Some(io::Error::from_raw_os_error(error)))
             }
         }
     }
 
-    pub fn r#type(&self) -> io::Result<SocketType> {
-        unsafe {
-            let fd = self.fd;
-            let mut val = 0;
-            let mut len = std::mem::size_of::<i32>() as u32;
-            let res = sock_getsockopt(
-                fd as u32,
-                SocketOptLevel::SolSocket as i32,
-                SocketOptName::SoType as i32,
-                &mut val,
-                &mut len,
-            );
-            if res != 0 {
-                Err(io::Error::from_raw_os_error(res as i32))
-            } else {
-                match val {
-                    1 => Ok(SocketType::Datagram),
-                    2 => Ok(SocketType::Stream),
-                    _ => Err(io::Error::from_raw_os_error(libc::EINVAL)),
+    /// Find out whether a nonblocking connect that has become writable
+    /// succeeded, using only WAMR calls.
+    ///
+    /// A connected socket has a peer address. Otherwise the pending error is
+    /// reported by the next receive.
+    pub fn connect_result(&self) -> io::Result<()> {
+        match self.get_peer() {
+            Ok(_) => Ok(()),
+            Err(e) if e.raw_os_error() == Some(libc::ENOTCONN) => {
+                let mut buf = [0u8; 1];
+                match self.recv(&mut buf) {
+                    Err(err) if err.kind() != io::ErrorKind::WouldBlock => Err(err),
+                    _ => Err(e),
                 }
             }
+            Err(e) => Err(e),
         }
     }
 
-    pub fn broadcast(&self) -> io::Result<bool> {
+    pub fn is_listener(&self) -> io::Result<bool> {
         unsafe {
             let fd = self.fd;
             let mut val = 0;
@@ -1156,7 +1220,7 @@ impl Socket {
             let res = sock_getsockopt(
                 fd as u32,
                 SocketOptLevel::SolSocket as i32,
-                SocketOptName::SoBroadcast as i32,
+                SocketOptName::SoAcceptconn as i32,
                 &mut val,
                 &mut len,
             );
@@ -1168,7 +1232,7 @@ impl Socket {
         }
     }
 
-    pub fn keepalive(&self) -> io::Result<bool> {
+    pub fn r#type(&self) -> io::Result<SocketType> {
         unsafe {
             let fd = self.fd;
             let mut val = 0;
@@ -1176,76 +1240,203 @@ impl Socket {
             let res = sock_getsockopt(
                 fd as u32,
                 SocketOptLevel::SolSocket as i32,
-                SocketOptName::SoKeepalive as i32,
+                SocketOptName::SoType as i32,
                 &mut val,
                 &mut len,
             );
             if res != 0 {
                 Err(io::Error::from_raw_os_error(res as i32))
             } else {
-                Ok(val != 0)
+                match val {
+                    1 => Ok(SocketType::Datagram),
+                    2 => Ok(SocketType::Stream),
+                    _ => Err(io::Error::from_raw_os_error(libc::EINVAL)),
+                }
             }
         }
     }
 
+    pub fn broadcast(&self) -> io::Result<bool> {
+        let mut val = false;
+        let errno = socket_wamr::wamr_sock_get_broadcast(self.fd as u32, &mut val);
+        wamr_result(errno, val)
+    }
+
+    pub fn keepalive(&self) -> io::Result<bool> {
+        let mut val = false;
+        let errno = socket_wamr::wamr_sock_get_keep_alive(self.fd as u32, &mut val);
+        wamr_result(errno, val)
+    }
+
     pub fn recv_buffer_size(&self) -> io::Result<usize> {
-        unsafe {
-            let fd = self.fd;
-            let mut val = 0;
-            let mut len = std::mem::size_of::<i32>() as u32;
-            let res = sock_getsockopt(
-                fd as u32,
-                SocketOptLevel::SolSocket as i32,
-                SocketOptName::SoRcvbuf as i32,
-                &mut val,
-                &mut len,
-            );
-            if res != 0 {
-                Err(io::Error::from_raw_os_error(res as i32))
-            } else {
-                Ok(val as usize)
-            }
-        }
+        let mut val: socket_wamr::WasiSize = 0;
+        let errno = socket_wamr::wamr_sock_get_recv_buf_size(self.fd as u32, &mut val);
+        wamr_result(errno, val as usize)
     }
 
     pub fn send_buffer_size(&self) -> io::Result<usize> {
-        unsafe {
-            let fd = self.fd;
-            let mut val = 0;
-            let mut len = std::mem::size_of::<i32>() as u32;
-            let res = sock_getsockopt(
-                fd as u32,
-                SocketOptLevel::SolSocket as i32,
-                SocketOptName::SoSndbuf as i32,
-                &mut val,
-                &mut len,
-            );
-            if res != 0 {
-                Err(io::Error::from_raw_os_error(res as i32))
-            } else {
-                Ok(val as usize)
-            }
-        }
+        let mut val: socket_wamr::WasiSize = 0;
+        let errno = socket_wamr::wamr_sock_get_send_buf_size(self.fd as u32, &mut val);
+        wamr_result(errno, val as usize)
     }
 
     pub fn reuse_address(&self) -> io::Result<bool> {
-        unsafe {
-            let fd = self.fd;
-            let mut val = 0;
-            let mut len = std::mem::size_of::<i32>() as u32;
-            let res = sock_getsockopt(
-                fd as u32,
-                SocketOptLevel::SolSocket as i32,
-                SocketOptName::SoReuseaddr as i32,
-                &mut val,
-                &mut len,
-            );
-            if res != 0 {
-                Err(io::Error::from_raw_os_error(res as i32))
-            } else {
-                Ok(val != 0)
+        let mut val = false;
+        let errno = socket_wamr::wamr_sock_get_reuse_addr(self.fd as u32, &mut val);
+        wamr_result(errno, val)
+    }
+
+    pub fn reuse_port(&self) -> io::Result<bool> {
+        let mut val = false;
+        let errno = socket_wamr::wamr_sock_get_reuse_port(self.fd as u32, &mut val);
+        wamr_result(errno, val)
+    }
+
+    pub fn set_broadcast(&self, broadcast: bool) -> io::Result<()> {
+        self.setsockopt_socket(socket_wamr::SocketOptName::SoBroadcast(broadcast))
+    }
+
+    /// Set `IP_TTL`. The runtime has no separate IPv6 hop limit option.
+    pub fn set_ttl(&self, ttl: u8) -> io::Result<()> {
+        self.setsockopt_socket(socket_wamr::SocketOptName::IpTtl(ttl))
+    }
+
+    pub fn ttl(&self) -> io::Result<u8> {
+        let mut val = 0;
+        let errno = socket_wamr::wamr_sock_get_ip_ttl(self.fd as u32, &mut val);
+        wamr_result(errno, val)
+    }
+
+    pub fn set_keepalive(&self, keepalive: bool) -> io::Result<()> {
+        self.setsockopt_socket(socket_wamr::SocketOptName::SoKeepalive(keepalive))
+    }
+
+    /// Set `SO_LINGER`, `None` disables lingering on close.
+    pub fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+        let opt = match linger {
+            Some(d) => {
+                socket_wamr::SocketOptName::SoLinger(true, secs_u32(d).min(i32::MAX as u32) as i32)
             }
-        }
+            None => socket_wamr::SocketOptName::SoLinger(false, 0),
+        };
+        self.setsockopt_socket(opt)
+    }
+
+    pub fn linger(&self) -> io::Result<Option<Duration>> {
+        let mut enabled = false;
+        let mut linger_s = 0;
+        let errno = socket_wamr::wamr_sock_get_linger(self.fd as u32, &mut enabled, &mut linger_s);
+        let linger = Duration::from_secs(linger_s.max(0) as u64);
+        wamr_result(errno, Some(linger).filter(|_| enabled))
+    }
+
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.setsockopt_socket(socket_wamr::SocketOptName::TcpNodelay(nodelay))
+    }
+
+    pub fn nodelay(&self) -> io::Result<bool> {
+        let mut val = false;
+        let errno = socket_wamr::wamr_sock_get_tcp_no_delay(self.fd as u32, &mut val);
+        wamr_result(errno, val)
+    }
+
+    pub fn set_quickack(&self, quickack: bool) -> io::Result<()> {
+        self.setsockopt_socket(socket_wamr::SocketOptName::TcpQuickack(quickack))
+    }
+
+    pub fn quickack(&self) -> io::Result<bool> {
+        let mut val = false;
+        let errno = socket_wamr::wamr_sock_get_tcp_quick_ack(self.fd as u32, &mut val);
+        wamr_result(errno, val)
+    }
+
+    /// Set `TCP_KEEPIDLE`, the idle time before the first keepalive probe.
+    pub fn set_tcp_keepidle(&self, time: Duration) -> io::Result<()> {
+        self.setsockopt_socket(socket_wamr::SocketOptName::TcpKeepidle(secs_u32(time)))
+    }
+
+    pub fn tcp_keepidle(&self) -> io::Result<Duration> {
+        let mut time_s = 0;
+        let errno = socket_wamr::wamr_sock_get_tcp_keep_idle(self.fd as u32, &mut time_s);
+        wamr_result(errno, Duration::from_secs(time_s as u64))
+    }
+
+    /// Set `TCP_KEEPINTVL`, the time between keepalive probes.
+    pub fn set_tcp_keepintvl(&self, interval: Duration) -> io::Result<()> {
+        self.setsockopt_socket(socket_wamr::SocketOptName::TcpKeepintvl(secs_u32(interval)))
+    }
+
+    pub fn tcp_keepintvl(&self) -> io::Result<Duration> {
+        let mut time_s = 0;
+        let errno = socket_wamr::wamr_sock_get_tcp_keep_intvl(self.fd as u32, &mut time_s);
+        wamr_result(errno, Duration::from_secs(time_s as u64))
+    }
+
+    /// Set `IPV6_V6ONLY`, has to be done before `bind`.
+    pub fn set_ipv6_only(&self, ipv6_only: bool) -> io::Result<()> {
+        let errno = socket_wamr::wamr_sock_set_ipv6_only(self.fd as u32, ipv6_only);
+        wamr_result(errno, ())
+    }
+
+    pub fn ipv6_only(&self) -> io::Result<bool> {
+        let mut val = false;
+        let errno = socket_wamr::wamr_sock_get_ipv6_only(self.fd as u32, &mut val);
+        wamr_result(errno, val)
+    }
+
+    /// Join the multicast group `multiaddr`.
+    ///
+    /// For IPv4 `interface` is the address of the local interface in network
+    /// byte order, for IPv6 it is the interface index. `0` lets the system
+    /// choose.
+    pub fn join_multicast(&self, multiaddr: IpAddr, interface: u32) -> io::Result<()> {
+        let multiaddr = socket_wamr::WasiAddrIp::from(multiaddr);
+        let errno =
+            socket_wamr::wamr_sock_set_ip_add_membership(self.fd as u32, &multiaddr, interface);
+        wamr_result(errno, ())
+    }
+
+    /// Leave the multicast group `multiaddr`, see [`Socket::join_multicast`].
+    pub fn leave_multicast(&self, multiaddr: IpAddr, interface: u32) -> io::Result<()> {
+        let multiaddr = socket_wamr::WasiAddrIp::from(multiaddr);
+        let errno =
+            socket_wamr::wamr_sock_set_ip_drop_membership(self.fd as u32, &multiaddr, interface);
+        wamr_result(errno, ())
+    }
+
+    /// Set `IP_MULTICAST_LOOP` or, when `ipv6` is `true`, `IPV6_MULTICAST_LOOP`.
+    pub fn set_multicast_loop(&self, ipv6: bool, multicast_loop: bool) -> io::Result<()> {
+        let errno =
+            socket_wamr::wamr_sock_set_ip_multicast_loop(self.fd as u32, ipv6, multicast_loop);
+        wamr_result(errno, ())
+    }
+
+    pub fn multicast_loop(&self, ipv6: bool) -> io::Result<bool> {
+        let mut val = false;
+        let errno = socket_wamr::wamr_sock_get_ip_multicast_loop(self.fd as u32, ipv6, &mut val);
+        wamr_result(errno, val)
+    }
+
+    pub fn set_multicast_ttl(&self, ttl: u8) -> io::Result<()> {
+        let errno = socket_wamr::wamr_sock_set_ip_multicast_ttl(self.fd as u32, ttl);
+        wamr_result(errno, ())
+    }
+
+    pub fn multicast_ttl(&self) -> io::Result<u8> {
+        let mut val = 0;
+        let errno = socket_wamr::wamr_sock_get_ip_multicast_ttl(self.fd as u32, &mut val);
+        wamr_result(errno, val)
+    }
+
+    /// Set `TCP_FASTOPEN_CONNECT`, this has to be done before `connect`.
+    pub fn set_tcp_fastopen_connect(&self, fastopen: bool) -> io::Result<()> {
+        self.setsockopt_socket(socket_wamr::SocketOptName::TcpFastopenConnect(fastopen))
+    }
+
+    pub fn tcp_fastopen_connect(&self) -> io::Result<bool> {
+        let mut val = false;
+        let errno = socket_wamr::wamr_sock_get_tcp_fastopen_connect(self.fd as u32, &mut val);
+        wamr_result(errno, val)
     }
 
     pub fn setsockopt<T>(
@@ -1294,40 +1485,26 @@ impl Socket {
             socket_wamr::SocketOptName::SoSndtimeo(value) => {
                 socket_wamr::wamr_sock_set_send_timeout(fd, value)
             }
-        };
-        if errno == 0 {
-            Ok(())
-        } else {
-            Err(io::Error::from_raw_os_error(errno as i32))
-        }
-    }
-
-    pub fn getsockopt_socket(&self, name: socket_wamr::SocketOptName) -> io::Result<()> {
-        let fd = self.fd as u32;
-        let errno = match name {
-            socket_wamr::SocketOptName::SoBroadcast(value) => {
-                socket_wamr::wamr_sock_set_broadcast(fd, value)
-            }
-            socket_wamr::SocketOptName::SoKeepalive(value) => {
-                socket_wamr::wamr_sock_set_keep_alive(fd, value)
+            socket_wamr::SocketOptName::SoLinger(enabled, linger_s) => {
+                socket_wamr::wamr_sock_set_linger(fd, enabled, linger_s)
             }
-            socket_wamr::SocketOptName::SoRcvbuf(value) => {
-                socket_wamr::wamr_sock_set_recv_buf_size(fd, value)
+            socket_wamr::SocketOptName::TcpNodelay(value) => {
+                socket_wamr::wamr_sock_set_tcp_no_delay(fd, value)
             }
-            socket_wamr::SocketOptName::SoReuseaddr(value) => {
-                socket_wamr::wamr_sock_set_reuse_addr(fd, value)
+            socket_wamr::SocketOptName::TcpQuickack(value) => {
+                socket_wamr::wamr_sock_set_tcp_quick_ack(fd, value)
             }
-            socket_wamr::SocketOptName::SoReuseport(value) => {
-                socket_wamr::wamr_sock_set_reuse_port(fd, value)
+            socket_wamr::SocketOptName::TcpKeepidle(value) => {
+                socket_wamr::wamr_sock_set_tcp_keep_idle(fd, value)
             }
-            socket_wamr::SocketOptName::SoSndbuf(value) => {
-                socket_wamr::wamr_sock_set_send_buf_size(fd, value)
+            socket_wamr::SocketOptName::TcpKeepintvl(value) => {
+                socket_wamr::wamr_sock_set_tcp_keep_intvl(fd, value)
             }
-            socket_wamr::SocketOptName::SoRcvtimeo(value) => {
-                socket_wamr::wamr_sock_set_recv_timeout(fd, value)
+            socket_wamr::SocketOptName::TcpFastopenConnect(value) => {
+                socket_wamr::wamr_sock_set_tcp_fastopen_connect(fd, value)
             }
-            socket_wamr::SocketOptName::SoSndtimeo(value) => {
-                socket_wamr::wamr_sock_set_send_timeout(fd, value)
+            socket_wamr::SocketOptName::IpTtl(value) => {
+                socket_wamr::wamr_sock_set_ip_ttl(fd, value)
             }
         };
         if errno == 0 {
@@ -1337,27 +1514,54 @@ impl Socket {
         }
     }
 
-    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
-        unsafe {
-            let flags = match how {
-                Shutdown::Read => 1,
-                Shutdown::Write => 2,
-                Shutdown::Both => 3,
-            };
-            let res = sock_shutdown(self.as_raw_fd() as u32, flags);
-            if res == 0 {
-                Ok(())
-            } else {
-                Err(io::Error::from_raw_os_error(res as i32))
+    /// Read the current value of the option `name` refers to.
+    ///
+    /// The value carried by `name` is ignored, the returned option holds the
+    /// value read from the socket. Timeouts are reported in microseconds with
+    /// `0` meaning no timeout, as for `setsockopt_socket`.
+    pub fn getsockopt_socket(
+        &self,
+        name: socket_wamr::SocketOptName,
+    ) -> io::Result<socket_wamr::SocketOptName> {
+        use socket_wamr::SocketOptName;
+        Ok(match name {
+            SocketOptName::SoBroadcast(_) => SocketOptName::SoBroadcast(self.broadcast()?),
+            SocketOptName::SoKeepalive(_) => SocketOptName::SoKeepalive(self.keepalive()?),
+            SocketOptName::SoRcvbuf(_) => {
+                SocketOptName::SoRcvbuf(self.recv_buffer_size()? as socket_wamr::WasiSize)
             }
-        }
+            SocketOptName::SoReuseaddr(_) => SocketOptName::SoReuseaddr(self.reuse_address()?),
+            SocketOptName::SoReuseport(_) => SocketOptName::SoReuseport(self.reuse_port()?),
+            SocketOptName::SoSndbuf(_) => {
+                SocketOptName::SoSndbuf(self.send_buffer_size()? as socket_wamr::WasiSize)
+            }
+            SocketOptName::SoRcvtimeo(_) => {
+                SocketOptName::SoRcvtimeo(timeout_micros(self.get_recv_timeout()?))
+            }
+            SocketOptName::SoSndtimeo(_) => {
+                SocketOptName::SoSndtimeo(timeout_micros(self.get_send_timeout()?))
+            }
+            SocketOptName::SoLinger(..) => match self.linger()? {
+                Some(linger) => SocketOptName::SoLinger(true, linger.as_secs() as i32),
+                None => SocketOptName::SoLinger(false, 0),
+            },
+            SocketOptName::TcpNodelay(_) => SocketOptName::TcpNodelay(self.nodelay()?),
+            SocketOptName::TcpQuickack(_) => SocketOptName::TcpQuickack(self.quickack()?),
+            SocketOptName::TcpKeepidle(_) => {
+                SocketOptName::TcpKeepidle(secs_u32(self.tcp_keepidle()?))
+            }
+            SocketOptName::TcpKeepintvl(_) => {
+                SocketOptName::TcpKeepintvl(secs_u32(self.tcp_keepintvl()?))
+            }
+            SocketOptName::TcpFastopenConnect(_) => {
+                SocketOptName::TcpFastopenConnect(self.tcp_fastopen_connect()?)
+            }
+            SocketOptName::IpTtl(_) => SocketOptName::IpTtl(self.ttl()?),
+        })
     }
-}
 
-impl Drop for Socket {
-    fn drop(&mut self) {
-        let _ = self.shutdown(Shutdown::Both);
-        unsafe { libc::close(self.fd) };
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        shutdown_fd(self.as_raw_fd(), how)
     }
 }
 
@@ -1368,15 +1572,94 @@ impl AsRawFd for Socket {
 }
 
 impl IntoRawFd for Socket {
+    /// Release the descriptor without closing it.
+    ///
+    /// # Panics
+    ///
+    /// If other handles created by [`Socket::try_clone`] are still alive, use
+    /// [`Socket::try_into_raw_fd`] to handle that case.
     fn into_raw_fd(self) -> RawFd {
-        let fd = self.fd;
-        std::mem::forget(self);
-        fd
+        self.try_into_raw_fd()
+            .expect("into_raw_fd called on a socket that still has clones")
     }
 }
 
 impl FromRawFd for Socket {
     unsafe fn from_raw_fd(fd: RawFd) -> Self {
-        Socket { fd }
+        Socket::owned(fd)
+    }
+}
+
+impl AsFd for Socket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        unsafe { BorrowedFd::borrow_raw(self.fd) }
+    }
+}
+
+impl From<OwnedFd> for Socket {
+    fn from(fd: OwnedFd) -> Self {
+        unsafe { Socket::from_raw_fd(fd.into_raw_fd()) }
+    }
+}
+
+impl TryFrom<Socket> for OwnedFd {
+    type Error = Socket;
+
+    /// Fails, giving the socket back, while other handles created by
+    /// [`Socket::try_clone`] are alive.
+    fn try_from(s: Socket) -> Result<Self, Socket> {
+        s.try_into_raw_fd()
+            .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn wasi_address_buf_from_ip() {
+        let mut v4 = WasiAddressBuf::from(IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3)));
+        let addr = v4.as_wasi_address();
+        assert_eq!(addr.size, 4);
+        assert_eq!(&v4.buf[..4], &[10, 1, 2, 3]);
+
+        let ip = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let mut v6 = WasiAddressBuf::from(IpAddr::V6(ip));
+        assert_eq!(v6.as_wasi_address().size, 16);
+        assert_eq!(&v6.buf[..16], &ip.octets());
+    }
+
+    #[test]
+    fn wasi_address_buf_to_socket_addr() {
+        let mut buf = WasiAddressBuf::new();
+        buf.buf[..6].copy_from_slice(&[1, 0, 192, 168, 0, 1]);
+        assert_eq!(
+            buf.to_socket_addr(53).unwrap(),
+            "192.168.0.1:53".parse::<SocketAddr>().unwrap()
+        );
+
+        let ip = Ipv6Addr::new(0xfe80, 0, 0, 0, 0x1234, 0, 0, 1);
+        let mut buf = WasiAddressBuf::new();
+        buf.buf[..2].copy_from_slice(&[2, 0]);
+        buf.buf[2..18].copy_from_slice(&ip.octets());
+        assert_eq!(
+            buf.to_socket_addr(8080).unwrap(),
+            SocketAddr::V6(SocketAddrV6::new(ip, 8080, 0, 0))
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn wasi_address_buf_rejects_malformed_source() {
+        let mut buf = WasiAddressBuf::new();
+        buf.buf[..2].copy_from_slice(&[7, 0]);
+        let err = buf.to_socket_addr(0).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EAFNOSUPPORT));
+
+        let mut buf = WasiAddressBuf::new();
+        buf.buf[..6].copy_from_slice(&[1, 0, 127, 0, 0, 1]);
+        let err = buf.to_socket_addr(u16::MAX as u32 + 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
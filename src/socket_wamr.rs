@@ -1,3 +1,5 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
 // struct addrinfo {
 //     int ai_flags;             /* Input flags.  */
 //     int ai_family;            /* Protocol family for socket.  */
@@ -98,6 +100,132 @@ impl WasiAddr {
     }
 }
 
+// Conversions between `std::net` addresses and the WAMR address structs.
+//
+// IPv6 addresses are stored as eight host-order segments, the runtime takes
+// care of the network byte order. The WAMR structs have no room for the IPv6
+// flow info and scope id, so they are dropped on the way in and zero on the
+// way out.
+
+impl From<Ipv4Addr> for WasiAddrIp4 {
+    fn from(ip: Ipv4Addr) -> Self {
+        let [n0, n1, n2, n3] = ip.octets();
+        WasiAddrIp4 { n0, n1, n2, n3 }
+    }
+}
+
+impl From<WasiAddrIp4> for Ipv4Addr {
+    fn from(ip: WasiAddrIp4) -> Self {
+        Ipv4Addr::new(ip.n0, ip.n1, ip.n2, ip.n3)
+    }
+}
+
+impl From<Ipv6Addr> for WasiAddrIp6 {
+    fn from(ip: Ipv6Addr) -> Self {
+        let [n0, n1, n2, n3, h0, h1, h2, h3] = ip.segments();
+        WasiAddrIp6 {
+            n0,
+            n1,
+            n2,
+            n3,
+            h0,
+            h1,
+            h2,
+            h3,
+        }
+    }
+}
+
+impl From<WasiAddrIp6> for Ipv6Addr {
+    fn from(ip: WasiAddrIp6) -> Self {
+        Ipv6Addr::new(ip.n0, ip.n1, ip.n2, ip.n3, ip.h0, ip.h1, ip.h2, ip.h3)
+    }
+}
+
+impl From<IpAddr> for WasiAddrIp {
+    fn from(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(ip) => WasiAddrIp {
+                kind: WasiAddrType::IPv4,
+                addr: WasiAddrIpUnion { ip4: ip.into() },
+            },
+            IpAddr::V6(ip) => WasiAddrIp {
+                kind: WasiAddrType::IPv6,
+                addr: WasiAddrIpUnion { ip6: ip.into() },
+            },
+        }
+    }
+}
+
+impl From<&WasiAddrIp> for IpAddr {
+    fn from(ip: &WasiAddrIp) -> Self {
+        // `kind` tells which union field was written.
+        match ip.kind {
+            WasiAddrType::IPv4 => IpAddr::V4(unsafe { ip.addr.ip4 }.into()),
+            WasiAddrType::IPv6 => IpAddr::V6(unsafe { ip.addr.ip6 }.into()),
+        }
+    }
+}
+
+impl From<SocketAddrV4> for WasiAddr {
+    fn from(addr: SocketAddrV4) -> Self {
+        WasiAddr {
+            kind: WasiAddrType::IPv4,
+            addr: WasiAddrUnion {
+                ip4: WasiAddrIp4Port {
+                    addr: (*addr.ip()).into(),
+                    port: addr.port(),
+                },
+            },
+        }
+    }
+}
+
+impl From<SocketAddrV6> for WasiAddr {
+    fn from(addr: SocketAddrV6) -> Self {
+        WasiAddr {
+            kind: WasiAddrType::IPv6,
+            addr: WasiAddrUnion {
+                ip6: WasiAddrIp6Port {
+                    addr: (*addr.ip()).into(),
+                    port: addr.port(),
+                },
+            },
+        }
+    }
+}
+
+impl From<SocketAddr> for WasiAddr {
+    fn from(addr: SocketAddr) -> Self {
+        match addr {
+            SocketAddr::V4(addr) => addr.into(),
+            SocketAddr::V6(addr) => addr.into(),
+        }
+    }
+}
+
+impl From<&WasiAddr> for SocketAddr {
+    fn from(addr: &WasiAddr) -> Self {
+        // `kind` tells which union field was written.
+        match addr.kind {
+            WasiAddrType::IPv4 => {
+                let ip4 = unsafe { addr.addr.ip4 };
+                SocketAddr::V4(SocketAddrV4::new(ip4.addr.into(), ip4.port))
+            }
+            WasiAddrType::IPv6 => {
+                let ip6 = unsafe { addr.addr.ip6 };
+                SocketAddr::V6(SocketAddrV6::new(ip6.addr.into(), ip6.port, 0, 0))
+            }
+        }
+    }
+}
+
+impl From<WasiAddr> for SocketAddr {
+    fn from(addr: WasiAddr) -> Self {
+        SocketAddr::from(&addr)
+    }
+}
+
 #[repr(C)]
 pub enum WasiAddressFamily {
     Inet4 = 0,
@@ -177,12 +305,18 @@ pub enum SocketOptName {
     SoRcvbuf(WasiSize),
     SoKeepalive(bool),
     // SoOobinline = 8,
-    // SoLinger = 9,
+    SoLinger(bool, i32),
     // SoRcvlowat = 10,
     SoRcvtimeo(u64),
     SoSndtimeo(u64),
     // SoAcceptconn = 13,
     // SoBindToDevice = 14,
+    TcpNodelay(bool),
+    TcpQuickack(bool),
+    TcpKeepidle(u32),
+    TcpKeepintvl(u32),
+    TcpFastopenConnect(bool),
+    IpTtl(u8),
 }
 
 #[link(wasm_import_module = "wasi_snapshot_preview1")]
@@ -473,4 +607,82 @@ pub fn wamr_sock_set_ipv6_only(fd: WasiFd, option: bool) -> WasiErrno {
 }
 pub fn wamr_sock_get_ipv6_only(fd: WasiFd, option: *mut bool) -> WasiErrno {
     unsafe { sock_get_ipv6_only(fd as i32, option as i32) as WasiErrno }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{offset_of, size_of};
+
+    #[test]
+    fn wasi_addr_layout() {
+        assert_eq!(size_of::<WasiAddrType>(), 4);
+        assert_eq!(size_of::<WasiAddrIp4Port>(), 6);
+        assert_eq!(size_of::<WasiAddrIp6Port>(), 18);
+        assert_eq!(offset_of!(WasiAddrIp6Port, port), 16);
+        assert_eq!(offset_of!(WasiAddr, addr), 4);
+        assert_eq!(size_of::<WasiAddr>(), 24);
+        assert_eq!(offset_of!(WasiAddrIp, addr), 4);
+        assert_eq!(size_of::<WasiAddrIp>(), 20);
+    }
+
+    #[test]
+    fn socket_addr_v4_to_wasi_addr() {
+        let addr: SocketAddr = "192.168.1.2:8080".parse().unwrap();
+        let wasi_addr = WasiAddr::from(addr);
+        assert!(wasi_addr.kind == WasiAddrType::IPv4);
+
+        // The port is stored as a host-order u16 right after the octets.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                (&wasi_addr.addr as *const WasiAddrUnion).cast::<u8>(),
+                size_of::<WasiAddrIp4Port>(),
+            )
+        };
+        let port = 8080u16.to_ne_bytes();
+        assert_eq!(bytes, &[192, 168, 1, 2, port[0], port[1]]);
+
+        assert_eq!(SocketAddr::from(&wasi_addr), addr);
+    }
+
+    #[test]
+    fn socket_addr_v6_to_wasi_addr() {
+        let ip = Ipv6Addr::new(0x2001, 0xdb8, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6);
+        let addr = SocketAddr::V6(SocketAddrV6::new(ip, 443, 7, 3));
+        let wasi_addr = WasiAddr::from(addr);
+        assert!(wasi_addr.kind == WasiAddrType::IPv6);
+
+        let ip6 = unsafe { wasi_addr.addr.ip6 };
+        let segments = [
+            ip6.addr.n0,
+            ip6.addr.n1,
+            ip6.addr.n2,
+            ip6.addr.n3,
+            ip6.addr.h0,
+            ip6.addr.h1,
+            ip6.addr.h2,
+            ip6.addr.h3,
+        ];
+        assert_eq!(segments, ip.segments());
+        assert_eq!(ip6.port, 443);
+
+        // Flow info and scope id do not survive the round trip.
+        assert_eq!(
+            SocketAddr::from(wasi_addr),
+            SocketAddr::V6(SocketAddrV6::new(ip, 443, 0, 0))
+        );
+    }
+
+    #[test]
+    fn ip_addr_to_wasi_addr_ip() {
+        let v4 = IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251));
+        let wasi_ip = WasiAddrIp::from(v4);
+        assert!(wasi_ip.kind == WasiAddrType::IPv4);
+        assert_eq!(IpAddr::from(&wasi_ip), v4);
+
+        let v6 = IpAddr::V6(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xfb));
+        let wasi_ip = WasiAddrIp::from(v6);
+        assert!(wasi_ip.kind == WasiAddrType::IPv6);
+        assert_eq!(IpAddr::from(&wasi_ip), v6);
+    }
+}